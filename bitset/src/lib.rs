@@ -0,0 +1,412 @@
+#![cfg_attr(feature = "simd", feature(portable_simd))]
+#![cfg_attr(test, feature(test))]
+
+//! Bitsets stored as 64 bit words, either a fixed number of words known at
+//! compile time ([`FixedBitSet`]) or any number chosen at runtime
+//! ([`BitSet`]). Both get their operations from the [`Bits`] trait.
+
+use std::{
+    hash::{Hash, Hasher},
+    ops::{BitAnd, BitOr, Range},
+};
+
+pub mod ops;
+
+/// The operations shared by every bitset. Operations between two sets
+/// treat words that only one of them has as zero in the other.
+pub trait Bits {
+    fn words(&self) -> &[u64];
+    fn words_mut(&mut self) -> &mut [u64];
+
+    /// How many bits the set can hold
+    fn capacity(&self) -> usize {
+        self.words().len() * 64
+    }
+
+    fn insert(&mut self, bit: usize) {
+        self.words_mut()[bit / 64] |= 1 << (bit % 64);
+    }
+
+    /// Sets every bit in `range`
+    fn insert_range(&mut self, range: Range<usize>) {
+        ops::set_range(self.words_mut(), range.start, range.end);
+    }
+
+    fn contains(&self, bit: usize) -> bool {
+        self.words()[bit / 64] & (1 << (bit % 64)) != 0
+    }
+
+    fn is_empty(&self) -> bool {
+        self.words().iter().all(|x| *x == 0)
+    }
+
+    fn count_ones(&self) -> usize {
+        ops::count_ones(self.words())
+    }
+
+    /// The lowest bit in the set
+    fn first_set(&self) -> Option<usize> {
+        ops::first_set(self.words())
+    }
+
+    fn intersects(&self, other: &Self) -> bool {
+        ops::intersects(self.words(), other.words())
+    }
+
+    fn is_subset(&self, other: &Self) -> bool {
+        ops::is_subset(self.words(), other.words())
+    }
+
+    fn intersect_with(&mut self, other: &Self) {
+        ops::and_assign(self.words_mut(), other.words());
+    }
+
+    /// Adds every bit of `other`. Sets that can be narrower than `other`
+    /// must grow first, or they lose its higher words.
+    fn union_with(&mut self, other: &Self) {
+        ops::or_assign(self.words_mut(), other.words());
+    }
+
+    /// The bits in the set, from the lowest up. This walks the words one
+    /// at a time, even with the `simd` feature.
+    fn iter(&self) -> Iter<'_> {
+        Iter {
+            words: self.words(),
+            offset: 0,
+            current: 0,
+        }
+    }
+}
+
+/// A bitset of `WORDS * 64` bits that lives on the stack
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FixedBitSet<const WORDS: usize>([u64; WORDS]);
+
+impl<const WORDS: usize> FixedBitSet<WORDS> {
+    pub const fn new() -> Self {
+        FixedBitSet([0; WORDS])
+    }
+
+    pub fn from_range(range: Range<usize>) -> Self {
+        let mut set = Self::new();
+        set.insert_range(range);
+        set
+    }
+}
+
+impl<const WORDS: usize> Default for FixedBitSet<WORDS> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const WORDS: usize> Bits for FixedBitSet<WORDS> {
+    fn words(&self) -> &[u64] {
+        &self.0
+    }
+
+    fn words_mut(&mut self) -> &mut [u64] {
+        &mut self.0
+    }
+}
+
+impl<const WORDS: usize> BitAnd for FixedBitSet<WORDS> {
+    type Output = Self;
+
+    fn bitand(mut self, rhs: Self) -> Self {
+        self.intersect_with(&rhs);
+        self
+    }
+}
+
+impl<const WORDS: usize> BitOr for FixedBitSet<WORDS> {
+    type Output = Self;
+
+    fn bitor(mut self, rhs: Self) -> Self {
+        self.union_with(&rhs);
+        self
+    }
+}
+
+/// A bitset whose size is picked at runtime. Sets holding the same bits
+/// are equal, and hash the same, however many words each has room for.
+#[derive(Debug, Clone)]
+pub struct BitSet {
+    words: Vec<u64>,
+}
+
+impl BitSet {
+    /// An empty set with room for at least `bits` bits
+    pub fn with_capacity(bits: usize) -> Self {
+        BitSet {
+            words: vec![0; bits.div_ceil(64)],
+        }
+    }
+
+    /// A set holding `range`, with room for nothing past it
+    pub fn from_range(range: Range<usize>) -> Self {
+        let mut set = Self::with_capacity(range.end);
+        set.insert_range(range);
+        set
+    }
+
+    /// The words up to the last one with a bit set
+    fn significant(&self) -> &[u64] {
+        let len = self
+            .words
+            .iter()
+            .rposition(|x| *x != 0)
+            .map_or(0, |i| i + 1);
+        &self.words[..len]
+    }
+}
+
+impl PartialEq for BitSet {
+    fn eq(&self, other: &Self) -> bool {
+        self.significant() == other.significant()
+    }
+}
+
+impl Eq for BitSet {}
+
+impl Hash for BitSet {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.significant().hash(state);
+    }
+}
+
+impl Bits for BitSet {
+    fn words(&self) -> &[u64] {
+        &self.words
+    }
+
+    fn words_mut(&mut self) -> &mut [u64] {
+        &mut self.words
+    }
+
+    fn union_with(&mut self, other: &Self) {
+        if self.words.len() < other.words.len() {
+            self.words.resize(other.words.len(), 0);
+        }
+        ops::or_assign(&mut self.words, &other.words);
+    }
+}
+
+/// Iterates over the bits of a set, see [`Bits::iter`]
+#[derive(Debug, Clone)]
+pub struct Iter<'a> {
+    words: &'a [u64],
+    offset: usize,
+    current: u64,
+}
+
+impl Iterator for Iter<'_> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        while self.current == 0 {
+            let (first, rest) = self.words.split_first()?;
+            self.current = *first;
+            self.words = rest;
+            self.offset += 64;
+        }
+
+        let bit = self.current.trailing_zeros() as usize;
+        self.current &= self.current - 1;
+        Some(self.offset - 64 + bit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate test;
+    use super::*;
+    use test::{black_box, Bencher};
+
+    #[test]
+    fn ranges_cross_word_boundaries() {
+        let set = BitSet::from_range(60..130);
+        assert_eq!(set.count_ones(), 70);
+        assert_eq!(set.first_set(), Some(60));
+        assert_eq!(set.iter().last(), Some(129));
+        assert!(!set.contains(59) && set.contains(64) && !set.contains(130));
+
+        let set = FixedBitSet::<2>::from_range(0..128);
+        assert_eq!(set.count_ones(), 128);
+        assert!(FixedBitSet::<2>::from_range(5..5).is_empty());
+    }
+
+    #[test]
+    fn matches_u128() {
+        for (a, b) in [
+            (0..10, 5..20),
+            (0..1, 127..128),
+            (3..100, 40..41),
+            (7..9, 9..12),
+        ] {
+            let to_u128 = |r: &Range<usize>| (r.start..r.end).fold(0u128, |x, i| x | 1 << i);
+            let (a_int, b_int) = (to_u128(&a), to_u128(&b));
+            let (a, b) = (
+                FixedBitSet::<2>::from_range(a),
+                FixedBitSet::<2>::from_range(b),
+            );
+
+            let both: Vec<_> = (a & b).iter().collect();
+            let expected: Vec<_> = (0..128).filter(|i| a_int & b_int & 1 << i != 0).collect();
+            assert_eq!(both, expected);
+
+            assert_eq!((a | b).count_ones() as u32, (a_int | b_int).count_ones());
+            assert_eq!(a.intersects(&b), a_int & b_int != 0);
+            assert_eq!(a.is_subset(&b), a_int & !b_int == 0);
+        }
+    }
+
+    #[test]
+    fn dynamic_sets_grow_past_128_bits() {
+        let mut a = BitSet::with_capacity(300);
+        let mut b = BitSet::with_capacity(300);
+        [5, 64, 250].iter().for_each(|x| a.insert(*x));
+        [64, 250, 299].iter().for_each(|x| b.insert(*x));
+
+        a.intersect_with(&b);
+        assert_eq!(a.iter().collect::<Vec<_>>(), [64, 250]);
+        assert!(a.is_subset(&b) && !b.is_subset(&a));
+
+        a.union_with(&b);
+        assert_eq!(a.count_ones(), 3);
+    }
+
+    #[test]
+    fn dynamic_sets_of_different_widths() {
+        let (narrow, wide) = (BitSet::from_range(0..10), BitSet::from_range(5..200));
+
+        let mut both = wide.clone();
+        both.intersect_with(&narrow);
+        assert_eq!(both.iter().collect::<Vec<_>>(), (5..10).collect::<Vec<_>>());
+
+        let mut either = narrow.clone();
+        either.union_with(&wide);
+        assert_eq!(either.count_ones(), 200);
+        assert_eq!(either.iter().last(), Some(199));
+
+        assert!(!wide.is_subset(&narrow));
+        assert!(BitSet::from_range(3..5).is_subset(&BitSet::from_range(0..130)));
+        assert!(BitSet::from_range(0..130).is_subset(&either));
+        assert!(narrow.intersects(&wide) && !BitSet::from_range(150..151).intersects(&narrow));
+
+        let hash = |set: &BitSet| {
+            let mut hasher = std::collections::hash_map::DefaultHasher::new();
+            set.hash(&mut hasher);
+            hasher.finish()
+        };
+        let mut roomy = BitSet::with_capacity(1000);
+        roomy.insert_range(5..200);
+        assert_eq!(roomy, wide);
+        assert_eq!(hash(&roomy), hash(&wide));
+        assert_ne!(roomy, narrow);
+        assert_eq!(BitSet::with_capacity(0), BitSet::with_capacity(500));
+    }
+
+    // Shaped like day 3: intersect three 52 item rucksacks and take the
+    // first shared item
+    const SACKS: [[u8; 8]; 3] = [
+        [3, 17, 29, 40, 41, 50, 8, 33],
+        [17, 2, 9, 44, 50, 1, 33, 12],
+        [22, 33, 17, 7, 19, 0, 50, 48],
+    ];
+
+    #[bench]
+    fn badge_u128(bench: &mut Bencher) {
+        bench.iter(|| {
+            black_box(SACKS)
+                .iter()
+                .map(|x| x.iter().fold(0u128, |set, i| set | 1 << i))
+                .reduce(|a, b| a & b)
+                .map(|x| x.trailing_zeros())
+        });
+    }
+
+    #[bench]
+    fn badge_fixed(bench: &mut Bencher) {
+        bench.iter(|| {
+            black_box(SACKS)
+                .iter()
+                .map(|x| {
+                    let mut set = FixedBitSet::<1>::new();
+                    x.iter().for_each(|i| set.insert(*i as usize));
+                    set
+                })
+                .reduce(|a, b| a & b)
+                .and_then(|x| x.first_set())
+        });
+    }
+
+    #[bench]
+    fn badge_dynamic(bench: &mut Bencher) {
+        bench.iter(|| {
+            black_box(SACKS)
+                .iter()
+                .map(|x| {
+                    let mut set = BitSet::with_capacity(52);
+                    x.iter().for_each(|i| set.insert(*i as usize));
+                    set
+                })
+                .reduce(|mut a, b| {
+                    a.intersect_with(&b);
+                    a
+                })
+                .and_then(|x| x.first_set())
+        });
+    }
+
+    // Shaped like day 4: build two section ranges and check for overlap
+    const PAIRS: [(usize, usize, usize, usize); 4] =
+        [(2, 8, 3, 7), (6, 6, 4, 6), (2, 4, 6, 8), (5, 99, 0, 98)];
+
+    #[bench]
+    fn overlap_u128(bench: &mut Bencher) {
+        bench.iter(|| {
+            black_box(PAIRS)
+                .iter()
+                .filter(|(a, b, c, d)| {
+                    let mask = |x: usize, y: usize| {
+                        u128::MAX.wrapping_shl(x as u32) & u128::MAX.wrapping_shr(127 - y as u32)
+                    };
+                    let (left, right) = (mask(*a, *b), mask(*c, *d));
+                    let count = (left & right).count_ones();
+                    count == left.count_ones() || count == right.count_ones()
+                })
+                .count()
+        });
+    }
+
+    #[bench]
+    fn overlap_fixed(bench: &mut Bencher) {
+        bench.iter(|| {
+            black_box(PAIRS)
+                .iter()
+                .filter(|(a, b, c, d)| {
+                    let left = FixedBitSet::<2>::from_range(*a..b + 1);
+                    let right = FixedBitSet::<2>::from_range(*c..d + 1);
+                    left.is_subset(&right) || right.is_subset(&left)
+                })
+                .count()
+        });
+    }
+
+    #[bench]
+    fn overlap_dynamic(bench: &mut Bencher) {
+        bench.iter(|| {
+            black_box(PAIRS)
+                .iter()
+                .filter(|(a, b, c, d)| {
+                    let mut left = BitSet::with_capacity(128);
+                    let mut right = BitSet::with_capacity(128);
+                    left.insert_range(*a..b + 1);
+                    right.insert_range(*c..d + 1);
+                    left.is_subset(&right) || right.is_subset(&left)
+                })
+                .count()
+        });
+    }
+}
@@ -0,0 +1,237 @@
+//! Word-by-word kernels shared by every bitset. With the `simd` feature the
+//! bulk operations, and the search for the first set bit, work on pairs of
+//! words at a time.
+//!
+//! They work on plain slices, so callers can apply them to any window of a
+//! set's words. The slices may have different lengths: words past the end
+//! of the shorter one count as zero.
+
+/// `a &= b`. Words of `a` that `b` doesn't have are cleared.
+pub fn and_assign(a: &mut [u64], b: &[u64]) {
+    let n = a.len().min(b.len());
+    kernels::and_assign(&mut a[..n], &b[..n]);
+    a[n..].fill(0);
+}
+
+/// `a |= b`. `a` can't grow here, so words of `b` past the end of `a` are
+/// left out; callers that can grow should do so first.
+pub fn or_assign(a: &mut [u64], b: &[u64]) {
+    let n = a.len().min(b.len());
+    kernels::or_assign(&mut a[..n], &b[..n]);
+}
+
+pub fn count_ones(a: &[u64]) -> usize {
+    kernels::count_ones(a)
+}
+
+/// Whether `a` and `b` share any bit
+pub fn intersects(a: &[u64], b: &[u64]) -> bool {
+    let n = a.len().min(b.len());
+    kernels::intersects(&a[..n], &b[..n])
+}
+
+/// Whether every bit of `a` is also set in `b`
+pub fn is_subset(a: &[u64], b: &[u64]) -> bool {
+    let n = a.len().min(b.len());
+    kernels::is_subset(&a[..n], &b[..n]) && a[n..].iter().all(|x| *x == 0)
+}
+
+/// The lowest set bit
+pub fn first_set(a: &[u64]) -> Option<usize> {
+    kernels::first_set(a)
+}
+
+/// Sets every bit in `start..end`
+pub fn set_range(a: &mut [u64], start: usize, end: usize) {
+    if start >= end {
+        return;
+    }
+
+    let (first, last) = (start / 64, (end - 1) / 64);
+    let head = u64::MAX << (start % 64);
+    let tail = u64::MAX >> (63 - (end - 1) % 64);
+
+    if first == last {
+        a[first] |= head & tail;
+        return;
+    }
+
+    a[first] |= head;
+    a[first + 1..last].iter_mut().for_each(|x| *x = u64::MAX);
+    a[last] |= tail;
+}
+
+#[cfg(feature = "simd")]
+use simd as kernels;
+
+#[cfg(not(feature = "simd"))]
+use scalar as kernels;
+
+/// The kernels a word at a time. Both slices must be the same length.
+#[cfg(any(test, not(feature = "simd")))]
+mod scalar {
+    pub fn and_assign(a: &mut [u64], b: &[u64]) {
+        a.iter_mut().zip(b).for_each(|(a, b)| *a &= b);
+    }
+
+    pub fn or_assign(a: &mut [u64], b: &[u64]) {
+        a.iter_mut().zip(b).for_each(|(a, b)| *a |= b);
+    }
+
+    pub fn count_ones(a: &[u64]) -> usize {
+        a.iter().map(|x| x.count_ones() as usize).sum()
+    }
+
+    pub fn intersects(a: &[u64], b: &[u64]) -> bool {
+        a.iter().zip(b).any(|(a, b)| a & b != 0)
+    }
+
+    pub fn is_subset(a: &[u64], b: &[u64]) -> bool {
+        a.iter().zip(b).all(|(a, b)| a & !b == 0)
+    }
+
+    pub fn first_set(a: &[u64]) -> Option<usize> {
+        a.iter()
+            .enumerate()
+            .find(|(_, word)| **word != 0)
+            .map(|(i, word)| i * 64 + word.trailing_zeros() as usize)
+    }
+}
+
+/// The kernels two words at a time. Both slices must be the same length,
+/// so that their chunks line up.
+#[cfg(feature = "simd")]
+mod simd {
+    use std::simd::{cmp::SimdPartialEq, num::SimdUint, u64x2};
+
+    pub fn and_assign(a: &mut [u64], b: &[u64]) {
+        debug_assert_eq!(a.len(), b.len());
+        let (a_chunks, a_rest) = a.as_chunks_mut::<2>();
+        let (b_chunks, b_rest) = b.as_chunks::<2>();
+        for (a, b) in a_chunks.iter_mut().zip(b_chunks) {
+            *a = (u64x2::from_array(*a) & u64x2::from_array(*b)).to_array();
+        }
+        a_rest.iter_mut().zip(b_rest).for_each(|(a, b)| *a &= b);
+    }
+
+    pub fn or_assign(a: &mut [u64], b: &[u64]) {
+        debug_assert_eq!(a.len(), b.len());
+        let (a_chunks, a_rest) = a.as_chunks_mut::<2>();
+        let (b_chunks, b_rest) = b.as_chunks::<2>();
+        for (a, b) in a_chunks.iter_mut().zip(b_chunks) {
+            *a = (u64x2::from_array(*a) | u64x2::from_array(*b)).to_array();
+        }
+        a_rest.iter_mut().zip(b_rest).for_each(|(a, b)| *a |= b);
+    }
+
+    pub fn count_ones(a: &[u64]) -> usize {
+        let (chunks, rest) = a.as_chunks::<2>();
+        let bulk: u64 = chunks
+            .iter()
+            .map(|x| u64x2::from_array(*x).count_ones().reduce_sum())
+            .sum();
+        bulk as usize + rest.iter().map(|x| x.count_ones() as usize).sum::<usize>()
+    }
+
+    pub fn intersects(a: &[u64], b: &[u64]) -> bool {
+        debug_assert_eq!(a.len(), b.len());
+        let (a_chunks, a_rest) = a.as_chunks::<2>();
+        let (b_chunks, b_rest) = b.as_chunks::<2>();
+        a_chunks.iter().zip(b_chunks).any(|(a, b)| {
+            (u64x2::from_array(*a) & u64x2::from_array(*b))
+                .simd_ne(u64x2::splat(0))
+                .any()
+        }) || a_rest.iter().zip(b_rest).any(|(a, b)| a & b != 0)
+    }
+
+    pub fn is_subset(a: &[u64], b: &[u64]) -> bool {
+        debug_assert_eq!(a.len(), b.len());
+        let (a_chunks, a_rest) = a.as_chunks::<2>();
+        let (b_chunks, b_rest) = b.as_chunks::<2>();
+        a_chunks.iter().zip(b_chunks).all(|(a, b)| {
+            (u64x2::from_array(*a) & !u64x2::from_array(*b))
+                .simd_eq(u64x2::splat(0))
+                .all()
+        }) && a_rest.iter().zip(b_rest).all(|(a, b)| a & !b == 0)
+    }
+
+    pub fn first_set(a: &[u64]) -> Option<usize> {
+        // Skip the empty pairs, then finish off a word at a time
+        let (chunks, _) = a.as_chunks::<2>();
+        let skip = 2 * chunks
+            .iter()
+            .take_while(|x| u64x2::from_array(**x).simd_eq(u64x2::splat(0)).all())
+            .count();
+        a[skip..]
+            .iter()
+            .enumerate()
+            .find(|(_, word)| **word != 0)
+            .map(|(i, word)| (skip + i) * 64 + word.trailing_zeros() as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    /// Bit `i` of a set, counting words past the end as zero
+    fn bit(a: &[u64], i: usize) -> bool {
+        a.get(i / 64).is_some_and(|x| x & 1 << (i % 64) != 0)
+    }
+
+    fn words() -> impl Strategy<Value = Vec<u64>> {
+        // Sparse words as well as random ones, so subsets turn up
+        let word = prop_oneof![any::<u64>(), Just(0), Just(u64::MAX), 0..16u64];
+        prop::collection::vec(word, 0..6)
+    }
+
+    proptest! {
+        #[test]
+        fn unequal_widths_count_as_zero(a in words(), b in words()) {
+            let bits = 64 * a.len().max(b.len());
+
+            let mut and = a.clone();
+            and_assign(&mut and, &b);
+            prop_assert_eq!(and.len(), a.len());
+            for i in 0..bits {
+                prop_assert_eq!(bit(&and, i), bit(&a, i) && bit(&b, i));
+            }
+
+            let mut or = a.clone();
+            or_assign(&mut or, &b);
+            for i in 0..64 * a.len() {
+                prop_assert_eq!(bit(&or, i), bit(&a, i) || bit(&b, i));
+            }
+
+            let shared = (0..bits).any(|i| bit(&a, i) && bit(&b, i));
+            let subset = (0..bits).all(|i| !bit(&a, i) || bit(&b, i));
+            prop_assert_eq!(intersects(&a, &b), shared);
+            prop_assert_eq!(is_subset(&a, &b), subset);
+            prop_assert_eq!(count_ones(&a), (0..bits).filter(|i| bit(&a, *i)).count());
+            prop_assert_eq!(first_set(&a), (0..bits).find(|i| bit(&a, *i)));
+        }
+
+        #[cfg(feature = "simd")]
+        #[test]
+        fn simd_matches_scalar(a in words(), b in words()) {
+            let n = a.len().min(b.len());
+            let (a, b) = (&a[..n], &b[..n]);
+
+            let (mut simd_and, mut scalar_and) = (a.to_vec(), a.to_vec());
+            simd::and_assign(&mut simd_and, b);
+            scalar::and_assign(&mut scalar_and, b);
+            prop_assert_eq!(simd_and, scalar_and);
+
+            let (mut simd_or, mut scalar_or) = (a.to_vec(), a.to_vec());
+            simd::or_assign(&mut simd_or, b);
+            scalar::or_assign(&mut scalar_or, b);
+            prop_assert_eq!(simd_or, scalar_or);
+
+            prop_assert_eq!(simd::count_ones(a), scalar::count_ones(a));
+            prop_assert_eq!(simd::first_set(a), scalar::first_set(a));
+            prop_assert_eq!(simd::intersects(a, b), scalar::intersects(a, b));
+            prop_assert_eq!(simd::is_subset(a, b), scalar::is_subset(a, b));
+        }
+    }
+}
@@ -0,0 +1,281 @@
+use std::{collections::HashMap, fmt::Display};
+use thiserror::Error;
+
+use crate::{
+    game::{Game, Shape},
+    round::RoundError,
+    Outcome,
+};
+
+/// How the second column of the strategy guide is read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Response {
+    /// The letter names the shape we should play
+    Shape(HashMap<char, Shape>),
+    /// The letter names how the round should end
+    Outcome(HashMap<char, Outcome>),
+}
+
+/// A mapping from the letters in the strategy guide to what they mean.
+///
+/// The textual form is two columns separated by a `;`, each column
+/// being a list of `<letter>=<name>` entries, e.g.
+/// `A=rock B=paper C=scissors; X=lose Y=draw Z=win`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Decoding {
+    pub opponent: HashMap<char, Shape>,
+    pub response: Response,
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum DecodeError {
+    #[error("Expected 2 columns separated by ';', but found {0}")]
+    ColumnCount(usize),
+    #[error("Column {0} doesn't map any letters")]
+    EmptyColumn(usize),
+    #[error(r#"Expected an entry like "A=rock", but found "{0}" instead"#)]
+    BadEntry(String),
+    #[error(r#"Expected one of the game's shapes, but found "{0}" instead"#)]
+    ExpectedShape(String),
+    #[error(r#"Expected a shape or an outcome (lose, draw or win), but found "{0}" instead"#)]
+    ExpectedShapeOrOutcome(String),
+    #[error("Letter '{0}' is mapped more than once in the same column")]
+    Duplicate(char),
+    #[error("The response column mixes shapes and outcomes")]
+    MixedResponse,
+}
+
+/// The letters used for the opponent's column of a game with `count` shapes,
+/// counting up from `A`
+pub fn opponent_letters(count: usize) -> Vec<char> {
+    ('A'..='Z').take(count).collect()
+}
+
+/// The letters used for our column of the guide when it holds `count` values,
+/// counting down to `Z`
+pub fn response_letters(count: usize) -> Vec<char> {
+    let mut letters: Vec<_> = ('A'..='Z').rev().take(count).collect();
+    letters.reverse();
+    letters
+}
+
+impl Decoding {
+    /// The decoding assumed by part 1: X, Y and Z are the shapes we play
+    pub fn part1(game: &Game) -> Self {
+        Decoding {
+            opponent: opponent_letters(game.shape_count())
+                .into_iter()
+                .zip(game.shapes())
+                .collect(),
+            response: Response::Shape(
+                response_letters(game.shape_count())
+                    .into_iter()
+                    .zip(game.shapes())
+                    .collect(),
+            ),
+        }
+    }
+
+    /// The decoding used by part 2: X, Y and Z are how the round should end
+    pub fn part2(game: &Game) -> Self {
+        use Outcome::*;
+        Decoding {
+            opponent: Self::part1(game).opponent,
+            response: Response::Outcome(HashMap::from([('X', Lose), ('Y', Draw), ('Z', Win)])),
+        }
+    }
+
+    /// Decodes a pair of letters into `(opponent, me)`
+    pub fn decode(
+        &self,
+        game: &Game,
+        opponent: char,
+        response: char,
+    ) -> Result<(Shape, Shape), RoundError> {
+        let unknown = || RoundError::UnknownResponse(response);
+        let opponent = *self
+            .opponent
+            .get(&opponent)
+            .ok_or(RoundError::UnknownOpponent(opponent))?;
+
+        let me = match &self.response {
+            Response::Shape(map) => *map.get(&response).ok_or_else(unknown)?,
+            Response::Outcome(map) => {
+                game.predict_move(opponent, *map.get(&response).ok_or_else(unknown)?)
+            }
+        };
+
+        Ok((opponent, me))
+    }
+
+    pub fn parse(s: &str, game: &Game) -> Result<Self, DecodeError> {
+        let columns: Vec<_> = s.split(';').collect();
+        let [opponent, response] = columns[..] else {
+            return Err(DecodeError::ColumnCount(columns.len()));
+        };
+
+        let opponent = parse_entries(opponent)?
+            .into_iter()
+            .map(|(letter, name)| match game.shape(name) {
+                Some(shape) => Ok((letter, shape)),
+                None => Err(DecodeError::ExpectedShape(name.to_owned())),
+            })
+            .collect::<Result<HashMap<_, _>, _>>()?;
+
+        let response = parse_entries(response)?;
+
+        if opponent.is_empty() {
+            return Err(DecodeError::EmptyColumn(1));
+        }
+
+        let Some(&(_, first)) = response.first() else {
+            return Err(DecodeError::EmptyColumn(2));
+        };
+
+        // The first entry decides whether the whole column is read
+        // as shapes or as outcomes
+        let response = if game.shape(first).is_some() {
+            Response::Shape(
+                response
+                    .into_iter()
+                    .map(|(letter, name)| match game.shape(name) {
+                        Some(shape) => Ok((letter, shape)),
+                        None if Outcome::from_name(name).is_some() => {
+                            Err(DecodeError::MixedResponse)
+                        }
+                        None => Err(DecodeError::ExpectedShapeOrOutcome(name.to_owned())),
+                    })
+                    .collect::<Result<_, _>>()?,
+            )
+        } else if Outcome::from_name(first).is_some() {
+            Response::Outcome(
+                response
+                    .into_iter()
+                    .map(|(letter, name)| match Outcome::from_name(name) {
+                        Some(outcome) => Ok((letter, outcome)),
+                        None if game.shape(name).is_some() => Err(DecodeError::MixedResponse),
+                        None => Err(DecodeError::ExpectedShapeOrOutcome(name.to_owned())),
+                    })
+                    .collect::<Result<_, _>>()?,
+            )
+        } else {
+            return Err(DecodeError::ExpectedShapeOrOutcome(first.to_owned()));
+        };
+
+        Ok(Decoding { opponent, response })
+    }
+
+    /// Formats the decoding in the same form [`Decoding::parse`] reads
+    pub fn display<'a>(&'a self, game: &'a Game) -> DisplayDecoding<'a> {
+        DisplayDecoding {
+            decoding: self,
+            game,
+        }
+    }
+}
+
+fn parse_entries(column: &str) -> Result<Vec<(char, &str)>, DecodeError> {
+    let mut seen = Vec::new();
+
+    column
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|x| !x.is_empty())
+        .map(|entry| {
+            let bad_entry = || DecodeError::BadEntry(entry.to_owned());
+            let (letter, name) = entry.split_once('=').ok_or_else(bad_entry)?;
+
+            let mut chars = letter.chars();
+            let (Some(letter), None) = (chars.next(), chars.next()) else {
+                return Err(bad_entry());
+            };
+
+            if seen.contains(&letter) {
+                return Err(DecodeError::Duplicate(letter));
+            }
+            seen.push(letter);
+
+            Ok((letter, name))
+        })
+        .collect()
+}
+
+pub struct DisplayDecoding<'a> {
+    decoding: &'a Decoding,
+    game: &'a Game,
+}
+
+fn write_column<T>(
+    f: &mut std::fmt::Formatter<'_>,
+    map: &HashMap<char, T>,
+    name: impl Fn(&T) -> String,
+) -> std::fmt::Result {
+    let mut entries: Vec<_> = map.iter().collect();
+    entries.sort_by_key(|(letter, _)| **letter);
+
+    for (i, (letter, value)) in entries.into_iter().enumerate() {
+        if i > 0 {
+            write!(f, " ")?;
+        }
+        write!(f, "{}={}", letter, name(value))?;
+    }
+
+    Ok(())
+}
+
+impl Display for DisplayDecoding<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let shape_name = |shape: &Shape| self.game.name(*shape).to_owned();
+
+        write_column(f, &self.decoding.opponent, shape_name)?;
+        write!(f, "; ")?;
+        match &self.decoding.response {
+            Response::Shape(map) => write_column(f, map, shape_name),
+            Response::Outcome(map) => write_column(f, map, Outcome::to_string),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtins_round_trip() {
+        for game in [Game::rps(), Game::rpsls()] {
+            for decoding in [Decoding::part1(&game), Decoding::part2(&game)] {
+                let text = decoding.display(&game).to_string();
+                assert_eq!(Decoding::parse(&text, &game), Ok(decoding));
+            }
+        }
+    }
+
+    #[test]
+    fn rps_builtins_use_the_puzzle_letters() {
+        let game = Game::rps();
+        assert_eq!(
+            Decoding::part1(&game).display(&game).to_string(),
+            "A=rock B=paper C=scissors; X=rock Y=paper Z=scissors"
+        );
+    }
+
+    #[test]
+    fn rejects_bad_specs() {
+        let game = Game::rps();
+        assert_eq!(
+            Decoding::parse("A=rock", &game),
+            Err(DecodeError::ColumnCount(1))
+        );
+        assert_eq!(
+            Decoding::parse("A=rock A=paper; X=win", &game),
+            Err(DecodeError::Duplicate('A'))
+        );
+        assert_eq!(
+            Decoding::parse("A=rock; X=win Y=rock", &game),
+            Err(DecodeError::MixedResponse)
+        );
+        assert_eq!(
+            Decoding::parse("A=win; X=win", &game),
+            Err(DecodeError::ExpectedShape("win".to_owned()))
+        );
+    }
+}
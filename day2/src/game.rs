@@ -0,0 +1,283 @@
+use std::str::FromStr;
+use thiserror::Error;
+
+use crate::Outcome;
+
+/// A shape in a [`Game`], identified by where it appears in the game's
+/// list of shapes
+#[derive(Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
+pub struct Shape(pub usize);
+
+/// A game of N shapes, each worth some points, and a dominance relation
+/// saying which shapes beat which.
+///
+/// Every pair of different shapes must be decided one way or the other,
+/// and every shape must both beat, and be beaten by, at least one other
+/// shape. This means there is always a move that wins, draws or loses
+/// against any shape.
+///
+/// The textual form is a line listing the shapes and their points,
+/// followed by one line per shape listing what it beats, e.g.
+///
+/// ```text
+/// rock=1 paper=2 scissors=3
+/// rock beats scissors
+/// paper beats rock
+/// scissors beats paper
+/// ```
+///
+/// Blank lines and lines starting with `#` are ignored.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Game {
+    names: Vec<String>,
+    scores: Vec<isize>,
+    // beats[a][b] is true if shape a beats shape b
+    beats: Vec<Vec<bool>>,
+}
+
+const RPS: &str = "\
+rock=1 paper=2 scissors=3
+rock beats scissors
+paper beats rock
+scissors beats paper
+";
+
+const RPSLS: &str = "\
+rock=1 paper=2 scissors=3 lizard=4 spock=5
+rock beats scissors lizard
+paper beats rock spock
+scissors beats paper lizard
+lizard beats paper spock
+spock beats rock scissors
+";
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum GameError {
+    #[error("The game doesn't list any shapes")]
+    NoShapes,
+    #[error(r#"Expected a shape like "rock=1", but found "{0}" instead"#)]
+    BadShape(String),
+    #[error("Shape '{0}' is listed more than once")]
+    DuplicateShape(String),
+    #[error(r#"Line {0}: expected "<shape> beats <shape>...""#)]
+    BadRule(usize),
+    #[error("Line {line}: '{name}' isn't one of the game's shapes")]
+    UnknownShape { line: usize, name: String },
+    #[error("'{0}' can't beat itself")]
+    BeatsItself(String),
+    #[error("'{0}' and '{1}' both beat each other")]
+    Contradiction(String, String),
+    #[error("Nothing decides between '{0}' and '{1}'")]
+    Undecided(String, String),
+    #[error("'{0}' doesn't beat anything")]
+    NeverWins(String),
+    #[error("Nothing beats '{0}'")]
+    NeverLoses(String),
+}
+
+impl Game {
+    /// Rock, paper, scissors
+    pub fn rps() -> Self {
+        RPS.parse().unwrap()
+    }
+
+    /// Rock, paper, scissors, lizard, Spock
+    pub fn rpsls() -> Self {
+        RPSLS.parse().unwrap()
+    }
+
+    pub fn builtin(name: &str) -> Option<Self> {
+        match name {
+            "rps" => Some(Self::rps()),
+            "rpsls" => Some(Self::rpsls()),
+            _ => None,
+        }
+    }
+
+    pub fn shape_count(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn shapes(&self) -> impl Iterator<Item = Shape> {
+        (0..self.shape_count()).map(Shape)
+    }
+
+    pub fn name(&self, shape: Shape) -> &str {
+        &self.names[shape.0]
+    }
+
+    pub fn shape(&self, name: &str) -> Option<Shape> {
+        let name = name.to_ascii_lowercase();
+        self.names.iter().position(|x| *x == name).map(Shape)
+    }
+
+    pub fn beats(&self, shape: Shape, other: Shape) -> bool {
+        self.beats[shape.0][other.0]
+    }
+
+    pub fn score_shape(&self, shape: Shape) -> isize {
+        self.scores[shape.0]
+    }
+
+    pub fn outcome(&self, me: Shape, opponent: Shape) -> Outcome {
+        if self.beats(me, opponent) {
+            Outcome::Win
+        } else if self.beats(opponent, me) {
+            Outcome::Lose
+        } else {
+            Outcome::Draw
+        }
+    }
+
+    pub fn score_round(&self, me: Shape, opponent: Shape) -> isize {
+        self.outcome(me, opponent).score()
+    }
+
+    pub fn score(&self, me: Shape, opponent: Shape) -> isize {
+        self.score_round(me, opponent) + self.score_shape(me)
+    }
+
+    /// Picks a move giving the `desire`d outcome against `opponent_move`.
+    /// When several shapes would do, the one worth the most points is picked.
+    pub fn predict_move(&self, opponent_move: Shape, desire: Outcome) -> Shape {
+        self.shapes()
+            .filter(|me| self.outcome(*me, opponent_move) == desire)
+            .max_by_key(|me| self.score_shape(*me))
+            .expect("every shape wins, draws and loses against something")
+    }
+
+    fn validate(&self) -> Result<(), GameError> {
+        let name = |shape: Shape| self.name(shape).to_owned();
+
+        for a in self.shapes() {
+            if self.beats(a, a) {
+                return Err(GameError::BeatsItself(name(a)));
+            }
+
+            for b in self.shapes().filter(|b| *b > a) {
+                match (self.beats(a, b), self.beats(b, a)) {
+                    (true, true) => return Err(GameError::Contradiction(name(a), name(b))),
+                    (false, false) => return Err(GameError::Undecided(name(a), name(b))),
+                    _ => {}
+                }
+            }
+
+            if !self.shapes().any(|b| self.beats(a, b)) {
+                return Err(GameError::NeverWins(name(a)));
+            }
+            if !self.shapes().any(|b| self.beats(b, a)) {
+                return Err(GameError::NeverLoses(name(a)));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for Game {
+    type Err = GameError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (i + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'));
+
+        let (_, shapes) = lines.next().ok_or(GameError::NoShapes)?;
+
+        let mut names: Vec<String> = Vec::new();
+        let mut scores = Vec::new();
+        for shape in shapes.split_ascii_whitespace() {
+            let bad_shape = || GameError::BadShape(shape.to_owned());
+            let (name, score) = shape.split_once('=').ok_or_else(bad_shape)?;
+            let score = score.parse().map_err(|_| bad_shape())?;
+
+            let name = name.to_ascii_lowercase();
+            if name.is_empty() {
+                return Err(bad_shape());
+            }
+            if names.contains(&name) {
+                return Err(GameError::DuplicateShape(name));
+            }
+
+            names.push(name);
+            scores.push(score);
+        }
+
+        let mut game = Game {
+            beats: vec![vec![false; names.len()]; names.len()],
+            names,
+            scores,
+        };
+
+        for (line, rule) in lines {
+            let lookup = |name: &str| {
+                game.shape(name).ok_or_else(|| GameError::UnknownShape {
+                    line,
+                    name: name.to_owned(),
+                })
+            };
+
+            let mut words = rule.split_ascii_whitespace();
+            let (Some(winner), Some("beats")) = (words.next(), words.next()) else {
+                return Err(GameError::BadRule(line));
+            };
+
+            let winner = lookup(winner)?;
+            let losers = words.map(lookup).collect::<Result<Vec<_>, _>>()?;
+            if losers.is_empty() {
+                return Err(GameError::BadRule(line));
+            }
+
+            for loser in losers {
+                game.beats[winner.0][loser.0] = true;
+            }
+        }
+
+        game.validate()?;
+        Ok(game)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rps_matches_the_puzzle() {
+        let game = Game::rps();
+        let [rock, paper, scissors] = ["rock", "paper", "scissors"].map(|x| game.shape(x).unwrap());
+
+        assert_eq!(game.score(paper, rock), 8);
+        assert_eq!(game.score(rock, paper), 1);
+        assert_eq!(game.score(scissors, scissors), 6);
+        assert_eq!(game.predict_move(rock, Outcome::Win), paper);
+    }
+
+    #[test]
+    fn rpsls_is_balanced() {
+        let game = Game::rpsls();
+        for shape in game.shapes() {
+            let wins = game.shapes().filter(|x| game.beats(shape, *x)).count();
+            assert_eq!(wins, 2);
+        }
+    }
+
+    #[test]
+    fn rejects_inconsistent_games() {
+        assert_eq!("".parse::<Game>(), Err(GameError::NoShapes));
+        assert_eq!(
+            "a=1 b=2\na beats b\nb beats a".parse::<Game>(),
+            Err(GameError::Contradiction("a".to_owned(), "b".to_owned()))
+        );
+        assert_eq!(
+            "a=1 b=2 c=3\na beats b\nb beats c".parse::<Game>(),
+            Err(GameError::Undecided("a".to_owned(), "c".to_owned()))
+        );
+        assert_eq!(
+            "a=1 b=2\na beats b".parse::<Game>(),
+            Err(GameError::NeverLoses("a".to_owned()))
+        );
+    }
+}
@@ -0,0 +1,208 @@
+use std::{collections::HashMap, str::FromStr};
+use thiserror::Error;
+
+use crate::{
+    decode::{opponent_letters, response_letters, Decoding, Response},
+    game::{Game, Shape},
+    round::Round,
+    Outcome,
+};
+
+/// Something we know about the score the guide is meant to produce
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Constraint {
+    /// The score of the whole guide
+    Total(isize),
+    /// The score of a single round, with rounds counted from 1
+    Round { round: usize, score: isize },
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ConstraintError {
+    #[error(r#"Expected a number, but found "{0}" instead"#)]
+    ExpectedNumber(String),
+    #[error("Rounds are counted from 1")]
+    ZeroRound,
+}
+
+impl FromStr for Constraint {
+    type Err = ConstraintError;
+
+    /// Parses either `<total>` or `<round>=<score>`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let number = |x: &str| {
+            x.trim()
+                .parse()
+                .map_err(|_| ConstraintError::ExpectedNumber(x.to_owned()))
+        };
+
+        match s.split_once('=') {
+            None => Ok(Constraint::Total(number(s)?)),
+            Some((round, score)) => {
+                let round: isize = number(round)?;
+                if round <= 0 {
+                    return Err(ConstraintError::ZeroRound);
+                }
+
+                Ok(Constraint::Round {
+                    round: round as usize,
+                    score: number(score)?,
+                })
+            }
+        }
+    }
+}
+
+/// A decoding which satisfies every constraint, along with the
+/// total score it gives the guide
+#[derive(Debug, Clone)]
+pub struct Interpretation {
+    pub decoding: Decoding,
+    pub total: isize,
+}
+
+/// Every ordering of `0..n`
+fn permutations(n: usize) -> Vec<Vec<usize>> {
+    if n == 0 {
+        return vec![Vec::new()];
+    }
+
+    permutations(n - 1)
+        .into_iter()
+        .flat_map(|perm| {
+            (0..n).map(move |i| {
+                let mut perm = perm.clone();
+                perm.insert(i, n - 1);
+                perm
+            })
+        })
+        .collect()
+}
+
+fn assign<T: Copy>(letters: &[char], values: &[T], perm: &[usize]) -> HashMap<char, T> {
+    letters
+        .iter()
+        .copied()
+        .zip(perm.iter().map(|i| values[*i]))
+        .collect()
+}
+
+/// Every way of reading the opponent's letters as shapes and our letters
+/// as either shapes or outcomes. For a game of N shapes that is N! × N!
+/// shape readings and N! × 3! outcome readings.
+pub fn interpretations(game: &Game) -> impl Iterator<Item = Decoding> + '_ {
+    use Outcome::*;
+
+    let n = game.shape_count();
+    let shapes: Vec<Shape> = game.shapes().collect();
+    let outcomes = [Lose, Draw, Win];
+
+    let shape_perms = permutations(n);
+    let outcome_perms = permutations(outcomes.len());
+
+    let responses: Vec<_> = shape_perms
+        .iter()
+        .map(|perm| Response::Shape(assign(&response_letters(n), &shapes, perm)))
+        .chain(outcome_perms.iter().map(|perm| {
+            Response::Outcome(assign(&response_letters(outcomes.len()), &outcomes, perm))
+        }))
+        .collect();
+
+    shape_perms.into_iter().flat_map(move |perm| {
+        let opponent = assign(&opponent_letters(n), &shapes, &perm);
+        responses.clone().into_iter().map(move |response| Decoding {
+            opponent: opponent.clone(),
+            response,
+        })
+    })
+}
+
+/// Finds every interpretation of the guide that is consistent with
+/// all of the `constraints`. An interpretation which can't decode every
+/// round is never consistent.
+pub fn consistent(
+    rounds: &[Round],
+    game: &Game,
+    constraints: &[Constraint],
+) -> Vec<Interpretation> {
+    interpretations(game)
+        .filter_map(|decoding| {
+            let scores = rounds
+                .iter()
+                .map(|round| {
+                    let (opponent, me) = round.decode(game, &decoding).ok()?;
+                    Some(game.score(me, opponent))
+                })
+                .collect::<Option<Vec<_>>>()?;
+            let total = scores.iter().sum();
+
+            let satisfied = constraints.iter().all(|constraint| match *constraint {
+                Constraint::Total(expected) => total == expected,
+                Constraint::Round { round, score } => scores.get(round - 1) == Some(&score),
+            });
+
+            satisfied.then_some(Interpretation { decoding, total })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example() -> Vec<Round> {
+        crate::round::parse_guide("A Y\nB X\nC Z\n", false)
+            .unwrap()
+            .items
+    }
+
+    #[test]
+    fn enumerates_every_interpretation() {
+        assert_eq!(interpretations(&Game::rps()).count(), 6 * (6 + 6));
+        assert_eq!(interpretations(&Game::rpsls()).count(), 120 * (120 + 6));
+    }
+
+    #[test]
+    fn finds_the_builtin_decodings() {
+        let game = Game::rps();
+
+        let part1 = consistent(&example(), &game, &[Constraint::Total(15)]);
+        assert!(part1.iter().any(|x| x.decoding == Decoding::part1(&game)));
+
+        let part2 = consistent(&example(), &game, &[Constraint::Total(12)]);
+        assert!(part2.iter().any(|x| x.decoding == Decoding::part2(&game)));
+    }
+
+    #[test]
+    fn round_constraints_narrow_the_search() {
+        let game = Game::rps();
+        let all = consistent(&example(), &game, &[Constraint::Total(15)]);
+        let narrowed = consistent(
+            &example(),
+            &game,
+            &[
+                Constraint::Total(15),
+                Constraint::Round { round: 1, score: 8 },
+                Constraint::Round { round: 2, score: 1 },
+            ],
+        );
+
+        // Round 1 scoring 8 and round 2 scoring 1 pins the opponent to
+        // A=rock B=paper C=scissors, leaving one shape reading and one
+        // outcome reading of our column
+        let mut found: Vec<_> = narrowed
+            .iter()
+            .map(|x| x.decoding.display(&game).to_string())
+            .collect();
+        found.sort();
+        assert_eq!(
+            found,
+            [
+                "A=rock B=paper C=scissors; X=lose Y=win Z=draw",
+                "A=rock B=paper C=scissors; X=rock Y=paper Z=scissors",
+            ]
+        );
+        assert!(narrowed.iter().all(|x| x.total == 15));
+        assert!(all.len() > narrowed.len());
+    }
+}
@@ -0,0 +1,133 @@
+use thiserror::Error;
+
+/// The score for each round of part 1, indexed by
+/// `(opponent - 'A') * 3 + (response - 'X')`
+pub const PART1: [u32; 9] = [4, 8, 3, 1, 5, 9, 7, 2, 6];
+
+/// The score for each round of part 2, indexed the same way as [`PART1`]
+pub const PART2: [u32; 9] = [3, 4, 8, 1, 5, 9, 2, 6, 7];
+
+#[derive(Debug, Error, PartialEq, Eq)]
+#[error(r#"Line {0}: expected a record of the form "<A-C> <X-Z>""#)]
+pub struct LutError(pub usize);
+
+#[inline(always)]
+fn lookup(record: &[u8], table: &[u32; 9], line: usize) -> Result<u32, LutError> {
+    let opponent = record[0].wrapping_sub(b'A');
+    let response = record[2].wrapping_sub(b'X');
+
+    if opponent > 2 || response > 2 || record[1] != b' ' {
+        return Err(LutError(line));
+    }
+
+    Ok(table[(opponent * 3 + response) as usize])
+}
+
+/// Scores a guide made of fixed 4 byte `"A X\n"` records. Only the
+/// puzzle's own letters are understood, and the final newline is optional.
+pub fn score(input: &[u8], table: &[u32; 9]) -> Result<u64, LutError> {
+    let records = input.chunks_exact(4);
+    let remainder = records.remainder();
+    let mut total = 0u64;
+
+    for (i, record) in records.enumerate() {
+        if record[3] != b'\n' {
+            return Err(LutError(i + 1));
+        }
+        total += lookup(record, table, i + 1)? as u64;
+    }
+
+    match remainder.len() {
+        0 => {}
+        3 => total += lookup(remainder, table, input.len() / 4 + 1)? as u64,
+        _ => return Err(LutError(input.len() / 4 + 1)),
+    }
+
+    Ok(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        decode::Decoding,
+        game::Game,
+        round::{decode_guide, parse_guide},
+    };
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    fn enum_score(input: &str, decoding: &Decoding, game: &Game) -> u64 {
+        let rounds = parse_guide(input, false).unwrap().items;
+        decode_guide(&rounds, game, decoding, false)
+            .unwrap()
+            .items
+            .iter()
+            .map(|(opponent, me)| game.score(*me, *opponent) as u64)
+            .sum()
+    }
+
+    #[test]
+    fn tables_match_the_game() {
+        let game = Game::rps();
+        for (table, decoding) in [
+            (&PART1, Decoding::part1(&game)),
+            (&PART2, Decoding::part2(&game)),
+        ] {
+            for opponent in 'A'..='C' {
+                for response in 'X'..='Z' {
+                    let record = format!("{} {}\n", opponent, response);
+                    assert_eq!(
+                        score(record.as_bytes(), table),
+                        Ok(enum_score(&record, &decoding, &game))
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn agrees_with_the_enum_scorer() {
+        let game = Game::rps();
+        let mut rng = StdRng::seed_from_u64(2);
+
+        for rounds in [0, 1, 2, 1000] {
+            let mut input: String = (0..rounds)
+                .map(|_| {
+                    let opponent = rng.gen_range(b'A'..=b'C') as char;
+                    let response = rng.gen_range(b'X'..=b'Z') as char;
+                    format!("{} {}\n", opponent, response)
+                })
+                .collect();
+
+            // Exercise a guide without the final newline too
+            if rounds % 2 == 1 {
+                input.pop();
+            }
+
+            assert_eq!(
+                score(input.as_bytes(), &PART1),
+                Ok(enum_score(&input, &Decoding::part1(&game), &game))
+            );
+            assert_eq!(
+                score(input.as_bytes(), &PART2),
+                Ok(enum_score(&input, &Decoding::part2(&game), &game))
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_records() {
+        assert_eq!(score(b"A X\nD X\n", &PART1), Err(LutError(2)));
+        assert_eq!(score(b"A X\na X\n", &PART1), Err(LutError(2)));
+        assert_eq!(score(b"A\tX\n", &PART1), Err(LutError(1)));
+        assert_eq!(score(b"A X Y\n", &PART1), Err(LutError(1)));
+        assert_eq!(score(b"A X\nB", &PART1), Err(LutError(2)));
+    }
+
+    #[test]
+    fn scores_the_puzzle_input() {
+        let input = include_str!("input.txt");
+        assert_eq!(score(input.as_bytes(), &PART1), Ok(11666));
+        assert_eq!(score(input.as_bytes(), &PART2), Ok(12767));
+    }
+}
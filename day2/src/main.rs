@@ -1,4 +1,21 @@
-use std::str::SplitAsciiWhitespace;
+use std::{fmt::Display, io::Write, time::Instant};
+
+use anyhow::{anyhow, bail, Context, Result};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use decode::Decoding;
+use game::{Game, Shape};
+use round::{Checked, Round};
+
+mod decode;
+mod game;
+mod infer;
+mod lut;
+mod round;
+mod sim;
+mod solve;
+mod tournament;
+mod trace;
 
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
 enum Outcome {
@@ -8,12 +25,12 @@ enum Outcome {
 }
 
 impl Outcome {
-    fn from_char(c: char) -> Option<Self> {
+    fn from_name(name: &str) -> Option<Self> {
         use Outcome::*;
-        match c {
-            'X' => Some(Lose),
-            'Y' => Some(Draw),
-            'Z' => Some(Win),
+        match name.to_ascii_lowercase().as_str() {
+            "lose" => Some(Lose),
+            "draw" => Some(Draw),
+            "win" => Some(Win),
             _ => None,
         }
     }
@@ -28,116 +45,458 @@ impl Outcome {
     }
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug)]
-enum Shape {
-    Rock,
-    Paper,
-    Scissors,
-}
-
-impl Shape {
-    fn from_char(c: char) -> Option<Self> {
-        use Shape::*;
-        match c {
-            'A' | 'X' => Some(Rock),
-            'B' | 'Y' => Some(Paper),
-            'C' | 'Z' => Some(Scissors),
-            _ => None,
-        }
+impl Display for Outcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Outcome::Lose => "lose",
+            Outcome::Draw => "draw",
+            Outcome::Win => "win",
+        };
+        write!(f, "{}", name)
     }
+}
 
-    const fn beats(self) -> Shape {
-        use Shape::*;
-        match self {
-            Rock => Scissors,
-            Paper => Rock,
-            Scissors => Paper,
-        }
+fn report_skipped(skipped: usize) {
+    if skipped > 0 {
+        println!("Skipped {} malformed line(s)", skipped);
     }
+}
 
-    const fn loses(self) -> Shape {
-        use Shape::*;
-        match self {
-            Rock => Paper,
-            Paper => Scissors,
-            Scissors => Rock,
+/// Decodes the guide into `(opponent, me)` pairs, reporting the lines
+/// skipped while either parsing or decoding it
+fn decode_guide(
+    rounds: &Checked<Round>,
+    game: &Game,
+    decoding: &Decoding,
+    lenient: bool,
+) -> Result<Vec<(Shape, Shape)>> {
+    let checked = round::decode_guide(&rounds.items, game, decoding, lenient)
+        .context("Failed to decode the strategy guide")?;
+    report_skipped(rounds.skipped.len() + checked.skipped.len());
+
+    Ok(checked.items)
+}
+
+fn score(guide: &[(Shape, Shape)], game: &Game) -> isize {
+    guide.iter().fold(0, |total, (opponent, me)| {
+        total + game.score(*me, *opponent)
+    })
+}
+
+fn part1(rounds: &Checked<Round>, game: &Game, lenient: bool) -> Result<()> {
+    let guide = decode_guide(rounds, game, &Decoding::part1(game), lenient)?;
+    println!("Score: {:?}", score(&guide, game));
+    Ok(())
+}
+
+fn part2(rounds: &Checked<Round>, game: &Game, lenient: bool) -> Result<()> {
+    let guide = decode_guide(rounds, game, &Decoding::part2(game), lenient)?;
+    println!("Score: {:?}", score(&guide, game));
+    Ok(())
+}
+
+fn infer(rounds: &[Round], game: &Game, constraints: &[&str]) -> Result<()> {
+    let constraints = constraints
+        .iter()
+        .map(|x| x.parse())
+        .collect::<Result<Vec<_>, _>>()
+        .context("Failed to parse constraint")?;
+
+    let found = infer::consistent(rounds, game, &constraints);
+    println!("{} consistent interpretation(s):", found.len());
+    for interpretation in found {
+        println!(
+            "{:>6}  {}",
+            interpretation.total,
+            interpretation.decoding.display(game)
+        );
+    }
+
+    Ok(())
+}
+
+/// The opponent's moves from the guide
+fn opponent_moves(guide: &[(Shape, Shape)]) -> Vec<Shape> {
+    guide.iter().map(|(opponent, _)| *opponent).collect()
+}
+
+/// Pairs up a command's `--option <value>` arguments
+fn options<'a>(args: &[&'a str]) -> Result<Vec<(&'a str, &'a str)>> {
+    if args.len() % 2 == 1 {
+        bail!("Expected a value after '{}'", args[args.len() - 1]);
+    }
+
+    Ok(args.chunks_exact(2).map(|x| (x[0], x[1])).collect())
+}
+
+fn simulate(guide: &[(Shape, Shape)], game: &Game, args: &[&str]) -> Result<()> {
+    let moves = opponent_moves(guide);
+
+    let mut opponent = "empirical";
+    let mut policy = "uniform";
+    let mut rounds = moves.len();
+    let mut trials = 10_000;
+    let mut seed = 2022;
+
+    for (arg, value) in options(args)? {
+        let number = || -> Result<usize> {
+            value
+                .parse()
+                .with_context(|| format!("Expected a number after '{}'", arg))
+        };
+
+        match arg {
+            "--opponent" => opponent = value,
+            "--policy" => policy = value,
+            "--rounds" => rounds = number()?,
+            "--trials" => trials = number()?,
+            "--seed" => seed = number()? as u64,
+            _ => bail!("Unknown simulation option '{}'", arg),
         }
     }
 
-    fn score_shape(self) -> isize {
-        use Shape::*;
-        match self {
-            Rock => 1,
-            Paper => 2,
-            Scissors => 3,
+    let opponent = sim::Opponent::parse(opponent, game, &moves).context("Invalid opponent")?;
+    let policy = sim::Policy::parse(policy, game).context("Invalid policy")?;
+
+    let exact = sim::expected_score(game, &opponent, &policy, rounds);
+    let estimate = sim::monte_carlo(
+        game,
+        &opponent,
+        &policy,
+        rounds,
+        trials,
+        &mut StdRng::seed_from_u64(seed),
+    );
+
+    println!("Expected score over {} rounds: {:.2}", rounds, exact);
+    println!(
+        "Monte Carlo ({} trials): {:.2}, std dev {:.2}, 95% CI {:.2}..{:.2}",
+        trials, estimate.mean, estimate.std_dev, estimate.low, estimate.high
+    );
+
+    Ok(())
+}
+
+fn print_strategy(game: &Game, label: &str, strategy: &[f64]) {
+    let strategy: Vec<_> = game
+        .shapes()
+        .map(|shape| format!("{} {:.3}", game.name(shape), strategy[shape.0]))
+        .collect();
+    println!("{:<8}{}", label, strategy.join(", "));
+}
+
+fn solve(guide: &[(Shape, Shape)], game: &Game, args: &[&str]) -> Result<()> {
+    let mut objective = solve::Objective::Score;
+    let mut against = "empirical";
+
+    for (arg, value) in options(args)? {
+        match arg {
+            "--objective" => {
+                objective = solve::Objective::from_name(value).ok_or_else(|| {
+                    anyhow!(
+                        "Unknown objective '{}', expected 'score' or 'margin'",
+                        value
+                    )
+                })?
+            }
+            "--against" => against = value,
+            _ => bail!("Unknown solver option '{}'", arg),
         }
     }
 
-    fn score_round(self, opponent: Self) -> isize {
-        use Shape::*;
-        match (self, opponent) {
-            (Rock, Paper) => Outcome::Lose.score(),
-            (Paper, Rock) => Outcome::Win.score(),
-            (Rock, Scissors) => Outcome::Win.score(),
-            (Scissors, Rock) => Outcome::Lose.score(),
-            (Paper, Scissors) => Outcome::Lose.score(),
-            (Scissors, Paper) => Outcome::Win.score(),
-            (x, y) if (x == y) => Outcome::Draw.score(),
-            _ => unreachable!(),
+    let theirs = match against {
+        "uniform" => sim::uniform(game),
+        "empirical" => sim::frequencies(&opponent_moves(guide), game)?,
+        weights => sim::parse_distribution(weights, game)?,
+    };
+
+    let payoffs = solve::payoff_matrix(game, objective);
+
+    println!("Payoffs (rows are our shape, columns are theirs):");
+    print!("{:>10}", "");
+    game.shapes().for_each(|x| print!("{:>10}", game.name(x)));
+    println!();
+    for me in game.shapes() {
+        print!("{:>10}", game.name(me));
+        payoffs[me.0].iter().for_each(|x| print!("{:>10}", x));
+        println!();
+    }
+
+    let eq = solve::solve_zero_sum(&payoffs);
+    println!();
+    println!("Equilibrium, worth {:.3} per round:", eq.value);
+    print_strategy(game, "ours", &eq.ours);
+    print_strategy(game, "theirs", &eq.theirs);
+
+    let best = solve::best_response(&payoffs, &theirs);
+    println!();
+    println!(
+        "Best response to {}: {}, worth {:.3} per round",
+        against,
+        game.name(best.shape),
+        best.payoffs[best.shape.0]
+    );
+    print_strategy(game, "payoffs", &best.payoffs);
+
+    Ok(())
+}
+
+fn trace(guide: &[(Shape, Shape)], game: &Game, args: &[&str]) -> Result<()> {
+    let steps = trace::trace(guide, game);
+    let mut out = std::io::stdout().lock();
+
+    match args {
+        [] => {
+            trace::write_table(&mut out, &steps, game)?;
+            writeln!(out)?;
+            trace::write_summary(&mut out, &trace::summarise(&steps, game), game)?;
         }
+        ["--csv"] => trace::write_csv(&mut out, &steps, game)?,
+        ["--summary"] => trace::write_summary(&mut out, &trace::summarise(&steps, game), game)?,
+        _ => bail!("Expected either '--csv' or '--summary' after 'trace'"),
+    }
+
+    Ok(())
+}
+
+fn tournament(guide: &[(Shape, Shape)], game: &Game, args: &[&str]) -> Result<()> {
+    use tournament::*;
+
+    let rounds = match args {
+        [] => 1000,
+        ["--rounds", rounds] => rounds
+            .parse()
+            .context("Expected a number after '--rounds'")?,
+        _ => bail!("Expected '--rounds <n>' after 'tournament'"),
+    };
+
+    let mut strategies: Vec<Box<dyn Strategy>> = vec![
+        Box::new(Always(Shape(0))),
+        Box::new(Cycle),
+        Box::new(BeatLast),
+        Box::<FrequencyCounter>::default(),
+    ];
+    if !guide.is_empty() {
+        let ours = guide.iter().map(|(_, me)| *me).collect();
+        strategies.push(Box::new(FollowGuide(ours)));
+    }
+
+    let names: Vec<_> = strategies.iter().map(|x| x.name(game)).collect();
+    let width = names.iter().map(String::len).max().unwrap_or(0);
+    let result = round_robin(game, &mut strategies, rounds);
+
+    println!("League table after {} rounds per match:", rounds);
+    println!(
+        "{:>4}  {:<width$} {:>3} {:>3} {:>3} {:>8} {:>8} {:>4}",
+        "", "strategy", "W", "D", "L", "for", "against", "pts"
+    );
+    for (rank, standing) in result.standings.iter().enumerate() {
+        println!(
+            "{:>4}  {:<width$} {:>3} {:>3} {:>3} {:>8} {:>8} {:>4}",
+            rank + 1,
+            names[standing.strategy],
+            standing.won,
+            standing.drawn,
+            standing.lost,
+            standing.score_for,
+            standing.score_against,
+            standing.points()
+        );
     }
 
-    pub fn score(self, opponent: Self) -> isize {
-        self.score_round(opponent) + self.score_shape()
+    println!();
+    println!("Head to head (row's score against column):");
+    print!("{:<width$}", "");
+    (1..=names.len()).for_each(|i| print!(" {:>8}", i));
+    println!();
+    for (i, row) in result.head_to_head.iter().enumerate() {
+        print!(
+            "{:<width$}",
+            format!("{} {}", i + 1, names[i]),
+            width = width + 2
+        );
+        for score in row {
+            match score {
+                Some(score) => print!(" {:>8}", score),
+                None => print!(" {:>8}", "-"),
+            }
+        }
+        println!();
     }
+
+    Ok(())
 }
 
-fn predict_move(opponent_move: Shape, desire: Outcome) -> Shape {
-    use Outcome::*;
-    match (opponent_move, desire) {
-        (x, Lose) => x.beats(),
-        (x, Win) => x.loses(),
-        (x, Draw) => x,
+/// Times the lookup table scorer against the game based one on a
+/// randomly generated guide
+fn bench(game: &Game, args: &[&str]) -> Result<()> {
+    let rounds: usize = match args {
+        [] => 100_000_000,
+        ["--rounds", rounds] => rounds
+            .parse()
+            .context("Expected a number after '--rounds'")?,
+        _ => bail!("Expected '--rounds <n>' after 'bench'"),
+    };
+
+    if *game != Game::rps() {
+        bail!("The lookup table scorer only understands the built-in rock, paper, scissors rules");
+    }
+
+    let mut rng = StdRng::seed_from_u64(2022);
+    let mut input = Vec::with_capacity(rounds * 4);
+    for _ in 0..rounds {
+        input.extend_from_slice(&[
+            rng.gen_range(b'A'..=b'C'),
+            b' ',
+            rng.gen_range(b'X'..=b'Z'),
+            b'\n',
+        ]);
+    }
+    let input = std::str::from_utf8(&input).expect("the guide is ASCII");
+
+    println!("Scoring {} generated rounds", rounds);
+
+    for (part, table, decoding) in [
+        (1, &lut::PART1, Decoding::part1(game)),
+        (2, &lut::PART2, Decoding::part2(game)),
+    ] {
+        let start = Instant::now();
+        let fast = lut::score(input.as_bytes(), table)?;
+        let fast_time = start.elapsed();
+
+        let start = Instant::now();
+        let mut slow = 0u64;
+        for (i, line) in input.lines().enumerate() {
+            let (opponent, me) = Round::parse(i + 1, line)?.decode(game, &decoding)?;
+            slow += game.score(me, opponent) as u64;
+        }
+        let slow_time = start.elapsed();
+
+        if fast != slow {
+            bail!("Part {}: the scorers disagree ({} != {})", part, fast, slow);
+        }
+
+        println!(
+            "Part {}: {} points, lookup table {:.2?}, game {:.2?} ({:.1}x)",
+            part,
+            fast,
+            fast_time,
+            slow_time,
+            slow_time.as_secs_f64() / fast_time.as_secs_f64()
+        );
     }
+
+    Ok(())
 }
 
-fn part1(input: &str) {
-    let score = input
-        .lines()
-        .map(|x| x.split_once(' '))
-        .flatten()
-        .map(|(l, r)| {
-            l.chars()
-                .next()
-                .and_then(Shape::from_char)
-                .zip(r.chars().next().and_then(Shape::from_char))
-        })
-        .flatten()
-        .fold(0, |total, (opponent, me)| total + me.score(opponent));
+const USAGE: &str = "\
+usage: day2 [options] [<command>]
+
+commands:
+    infer [<total> | <round>=<score>]...
+                            find every mapping consistent with the given scores
+    simulate [--opponent <model>] [--policy <policy>] [--rounds <n>] [--trials <n>] [--seed <n>]
+                            compute the expected score against a random opponent.
+                            <model> is uniform, empirical, markov, fixed:<weights> or
+                            markov:<weights>/<weights>/..., <policy> is uniform, beat-last,
+                            pure:<shape> or fixed:<weights>
+    trace [--csv | --summary]
+                            show where each round's points come from, as a table
+                            followed by a summary, as CSV, or just the summary
+    tournament [--rounds <n>]
+                            play the built-in strategies against each other
+    bench [--rounds <n>]    compare the lookup table scorer with the game based one on
+                            a generated guide (default: 100,000,000 rounds)
+    solve [--objective <score | margin>] [--against <uniform | empirical | weights>]
+                            find the equilibrium strategy, and the best response to
+                            an opponent
+
+options:
+    --game <rps | rpsls>    play one of the built-in games (default: rps)
+    --game-file <path>      read the game description from a file
+    --mapping <spec>        decode the guide with e.g. \"A=rock B=paper C=scissors; X=lose Y=draw Z=win\"
+    --mapping-file <path>   read the mapping from a file
+    --input <path>          read the strategy guide from a file instead of the puzzle input
+    --lenient               skip malformed lines in the guide instead of failing";
+
+fn expect_value(args: &mut impl Iterator<Item = String>, flag: &str) -> Result<String> {
+    args.next()
+        .ok_or_else(|| anyhow!("Expected a value after '{}'", flag))
+}
 
-    println!("Score: {:?}", score);
+fn read_file(path: &str) -> Result<String> {
+    std::fs::read_to_string(path).with_context(|| format!("Couldn't read '{}'", path))
 }
 
-fn part2(input: &str) {
-    let score = input
-        .lines()
-        .map(|x| x.split_once(' '))
-        .flatten()
-        .map(|(l, r)| {
-            l.chars()
-                .next()
-                .and_then(Shape::from_char)
-                .zip(r.chars().next().and_then(Outcome::from_char))
-        })
-        .flatten()
-        .map(|(theirs, desire)| (theirs, predict_move(theirs, desire)))
-        .fold(0, |total, (opponent, me)| total + me.score(opponent));
+fn run(input: &str) -> Result<()> {
+    let mut game = Game::rps();
+    let mut mapping = None;
+    let mut lenient = false;
+    let mut guide = None;
+    let mut command = Vec::new();
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--game" => {
+                let name = expect_value(&mut args, &arg)?;
+                game = Game::builtin(&name).ok_or_else(|| {
+                    anyhow!(
+                        "Unknown game '{}', the built-in games are 'rps' and 'rpsls'",
+                        name
+                    )
+                })?;
+            }
+            "--game-file" => {
+                let path = expect_value(&mut args, &arg)?;
+                game = read_file(&path)?
+                    .parse()
+                    .context("Failed to parse game description")?;
+            }
+            "--mapping" => mapping = Some(expect_value(&mut args, &arg)?),
+            "--mapping-file" => mapping = Some(read_file(&expect_value(&mut args, &arg)?)?),
+            "--lenient" => lenient = true,
+            "--input" => guide = Some(read_file(&expect_value(&mut args, &arg)?)?),
+            _ => command.push(arg),
+        }
+    }
+
+    let mapping = mapping
+        .map(|spec| Decoding::parse(&spec, &game))
+        .transpose()
+        .context("Failed to parse mapping")?;
+
+    let rounds = round::parse_guide(guide.as_deref().unwrap_or(input), lenient)
+        .context("Failed to parse the strategy guide")?;
+
+    let decode = |decoding: Option<Decoding>| {
+        let decoding = decoding.unwrap_or_else(|| Decoding::part1(&game));
+        decode_guide(&rounds, &game, &decoding, lenient)
+    };
+
+    let command: Vec<_> = command.iter().map(String::as_str).collect();
+    match (&command[..], mapping) {
+        ([], None) => {
+            part1(&rounds, &game, lenient)?;
+            part2(&rounds, &game, lenient)?;
+        }
+        ([], Some(decoding)) => println!("Score: {:?}", score(&decode(Some(decoding))?, &game)),
+        (["infer", constraints @ ..], None) => {
+            report_skipped(rounds.skipped.len());
+            infer(&rounds.items, &game, constraints)?
+        }
+        (["simulate", args @ ..], decoding) => simulate(&decode(decoding)?, &game, args)?,
+        (["solve", args @ ..], decoding) => solve(&decode(decoding)?, &game, args)?,
+        (["trace", args @ ..], decoding) => trace(&decode(decoding)?, &game, args)?,
+        (["tournament", args @ ..], decoding) => tournament(&decode(decoding)?, &game, args)?,
+        (["bench", args @ ..], None) => bench(&game, args)?,
+        _ => bail!("{}", USAGE),
+    }
 
-    println!("Score: {:?}", score);
+    Ok(())
 }
 
-fn main() {
+fn main() -> Result<()> {
     let input = include_str!("input.txt");
-    part1(input);
-    part2(input);
+    run(input)
 }
@@ -0,0 +1,212 @@
+use thiserror::Error;
+
+use crate::{
+    decode::Decoding,
+    game::{Game, Shape},
+};
+
+/// A line of the strategy guide, before its letters have been decoded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Round {
+    /// The line the round was read from, counting from 1
+    pub line: usize,
+    pub opponent: char,
+    pub response: char,
+}
+
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+pub enum RoundError {
+    #[error("The line is empty")]
+    Empty,
+    #[error("Expected the opponent's move and ours, but only found one column")]
+    MissingResponse,
+    #[error("Expected a single space between the columns, but found {0:?}")]
+    BadSeparator(String),
+    #[error(r#"Expected a single letter, but found "{0}" instead"#)]
+    NotALetter(String),
+    #[error(r#"Unexpected third column "{0}""#)]
+    ExtraColumn(String),
+    #[error("Unexpected whitespace at the end of the line")]
+    TrailingWhitespace,
+    #[error("The opponent's letter '{0}' isn't in the mapping")]
+    UnknownOpponent(char),
+    #[error("Our letter '{0}' isn't in the mapping")]
+    UnknownResponse(char),
+}
+
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+#[error("Line {line}: {error}")]
+pub struct LineError {
+    pub line: usize,
+    pub error: RoundError,
+}
+
+/// Splits `s` at the start of its first run of whitespace
+fn split_whitespace_run(s: &str) -> (&str, &str) {
+    match s.find(char::is_whitespace) {
+        Some(i) => s.split_at(i),
+        None => (s, ""),
+    }
+}
+
+fn letter(column: &str) -> Result<char, RoundError> {
+    let mut chars = column.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if c.is_alphabetic() => Ok(c),
+        _ => Err(RoundError::NotALetter(column.to_owned())),
+    }
+}
+
+impl Round {
+    /// Parses a line of exactly the form `"<letter> <letter>"`
+    pub fn parse(line: usize, s: &str) -> Result<Self, LineError> {
+        Self::parse_columns(s)
+            .map(|(opponent, response)| Round {
+                line,
+                opponent,
+                response,
+            })
+            .map_err(|error| LineError { line, error })
+    }
+
+    fn parse_columns(s: &str) -> Result<(char, char), RoundError> {
+        if s.is_empty() {
+            return Err(RoundError::Empty);
+        }
+
+        let (opponent, rest) = split_whitespace_run(s);
+        let opponent = letter(opponent)?;
+
+        let response_start = rest.find(|c: char| !c.is_whitespace());
+        let Some(response_start) = response_start else {
+            return Err(RoundError::MissingResponse);
+        };
+
+        let separator = &rest[..response_start];
+        if separator != " " {
+            return Err(RoundError::BadSeparator(separator.to_owned()));
+        }
+
+        let (response, extra) = split_whitespace_run(&rest[response_start..]);
+        let response = letter(response)?;
+
+        match extra.trim() {
+            "" if extra.is_empty() => Ok((opponent, response)),
+            "" => Err(RoundError::TrailingWhitespace),
+            column => Err(RoundError::ExtraColumn(column.to_owned())),
+        }
+    }
+
+    /// Decodes the round into `(opponent, me)`
+    pub fn decode(&self, game: &Game, decoding: &Decoding) -> Result<(Shape, Shape), LineError> {
+        decoding
+            .decode(game, self.opponent, self.response)
+            .map_err(|error| LineError {
+                line: self.line,
+                error,
+            })
+    }
+}
+
+/// The lines that could be used, and the ones skipped in lenient mode
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Checked<T> {
+    pub items: Vec<T>,
+    pub skipped: Vec<LineError>,
+}
+
+/// Collects per-line results. Unless `lenient` is set, the first bad
+/// line fails the whole guide.
+pub fn check_lines<T>(
+    results: impl IntoIterator<Item = Result<T, LineError>>,
+    lenient: bool,
+) -> Result<Checked<T>, LineError> {
+    let mut checked = Checked {
+        items: Vec::new(),
+        skipped: Vec::new(),
+    };
+
+    for result in results {
+        match result {
+            Ok(item) => checked.items.push(item),
+            Err(err) if lenient => checked.skipped.push(err),
+            Err(err) => return Err(err),
+        }
+    }
+
+    Ok(checked)
+}
+
+pub fn parse_guide(input: &str, lenient: bool) -> Result<Checked<Round>, LineError> {
+    check_lines(
+        input
+            .lines()
+            .enumerate()
+            .map(|(i, line)| Round::parse(i + 1, line)),
+        lenient,
+    )
+}
+
+pub fn decode_guide(
+    rounds: &[Round],
+    game: &Game,
+    decoding: &Decoding,
+    lenient: bool,
+) -> Result<Checked<(Shape, Shape)>, LineError> {
+    check_lines(
+        rounds.iter().map(|round| round.decode(game, decoding)),
+        lenient,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> Result<(char, char), RoundError> {
+        Round::parse_columns(s)
+    }
+
+    #[test]
+    fn parses_well_formed_rounds() {
+        assert_eq!(parse("A Y"), Ok(('A', 'Y')));
+        assert_eq!(parse("a y"), Ok(('a', 'y')));
+    }
+
+    #[test]
+    fn rejects_malformed_rounds() {
+        assert_eq!(parse(""), Err(RoundError::Empty));
+        assert_eq!(parse("A"), Err(RoundError::MissingResponse));
+        assert_eq!(parse("A "), Err(RoundError::MissingResponse));
+        assert_eq!(
+            parse("A\tY"),
+            Err(RoundError::BadSeparator("\t".to_owned()))
+        );
+        assert_eq!(
+            parse("A  Y"),
+            Err(RoundError::BadSeparator("  ".to_owned()))
+        );
+        assert_eq!(parse("AB Y"), Err(RoundError::NotALetter("AB".to_owned())));
+        assert_eq!(parse(" Y"), Err(RoundError::NotALetter("".to_owned())));
+        assert_eq!(parse("A Y Z"), Err(RoundError::ExtraColumn("Z".to_owned())));
+        assert_eq!(parse("A Y "), Err(RoundError::TrailingWhitespace));
+    }
+
+    #[test]
+    fn unmapped_letters_are_reported_with_their_line() {
+        let game = Game::rps();
+        let rounds = parse_guide("A Y\nb X\nC Z\n", false).unwrap().items;
+
+        assert_eq!(
+            decode_guide(&rounds, &game, &Decoding::part1(&game), false),
+            Err(LineError {
+                line: 2,
+                error: RoundError::UnknownOpponent('b')
+            })
+        );
+
+        let checked = decode_guide(&rounds, &game, &Decoding::part1(&game), true).unwrap();
+        assert_eq!(checked.items.len(), 2);
+        assert_eq!(checked.skipped.len(), 1);
+    }
+}
@@ -0,0 +1,428 @@
+use rand::Rng;
+use thiserror::Error;
+
+use crate::{
+    game::{Game, Shape},
+    Outcome,
+};
+
+/// A probability for each of a game's shapes, indexed by shape
+pub type Distribution = Vec<f64>;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum SimError {
+    #[error("Expected {expected} probabilities, one per shape, but found {found}")]
+    WrongLength { expected: usize, found: usize },
+    #[error(r#"Expected a probability, but found "{0}" instead"#)]
+    BadProbability(String),
+    #[error("Probabilities can't be negative")]
+    Negative,
+    #[error("Probabilities must be finite numbers")]
+    NotFinite,
+    #[error("The probabilities are too large to add up")]
+    Overflow,
+    #[error("At least one probability must be above zero")]
+    AllZero,
+    #[error("Expected {expected} rows in the transition matrix, but found {found}")]
+    WrongRowCount { expected: usize, found: usize },
+    #[error("The guide doesn't contain any moves to learn from")]
+    NoMoves,
+    #[error(r#"Unknown {kind} "{spec}""#)]
+    UnknownSpec { kind: &'static str, spec: String },
+}
+
+/// Parses a comma separated list of weights, and normalises them so
+/// they sum to 1
+pub fn parse_distribution(s: &str, game: &Game) -> Result<Distribution, SimError> {
+    let weights = s
+        .split(',')
+        .map(|x| {
+            x.trim()
+                .parse::<f64>()
+                .map_err(|_| SimError::BadProbability(x.to_owned()))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    normalise(weights, game.shape_count())
+}
+
+fn normalise(weights: Vec<f64>, expected: usize) -> Result<Distribution, SimError> {
+    if weights.len() != expected {
+        return Err(SimError::WrongLength {
+            expected,
+            found: weights.len(),
+        });
+    }
+
+    if weights.iter().any(|x| !x.is_finite()) {
+        return Err(SimError::NotFinite);
+    }
+
+    if weights.iter().any(|x| *x < 0.0) {
+        return Err(SimError::Negative);
+    }
+
+    let total: f64 = weights.iter().sum();
+    if !total.is_finite() {
+        return Err(SimError::Overflow);
+    }
+    if total == 0.0 {
+        return Err(SimError::AllZero);
+    }
+
+    Ok(weights.into_iter().map(|x| x / total).collect())
+}
+
+fn counts_to_distribution(counts: Vec<usize>) -> Result<Distribution, SimError> {
+    let len = counts.len();
+    normalise(counts.into_iter().map(|x| x as f64).collect(), len).map_err(|_| SimError::NoMoves)
+}
+
+/// How the opponent picks their moves. A fixed distribution is a Markov
+/// chain where every row of the transition matrix is the same.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Opponent {
+    initial: Distribution,
+    // transitions[a][b] is the chance of playing b after playing a
+    transitions: Vec<Distribution>,
+}
+
+impl Opponent {
+    pub fn fixed(distribution: Distribution) -> Self {
+        Opponent {
+            transitions: vec![distribution.clone(); distribution.len()],
+            initial: distribution,
+        }
+    }
+
+    pub fn markov(initial: Distribution, transitions: Vec<Distribution>) -> Self {
+        Opponent {
+            initial,
+            transitions,
+        }
+    }
+
+    /// Plays each shape as often as it appears in `moves`
+    pub fn empirical(moves: &[Shape], game: &Game) -> Result<Self, SimError> {
+        Ok(Self::fixed(frequencies(moves, game)?))
+    }
+
+    /// Learns how often each shape follows each other shape in `moves`.
+    /// Shapes which are never followed by anything fall back to the
+    /// overall frequencies.
+    pub fn empirical_markov(moves: &[Shape], game: &Game) -> Result<Self, SimError> {
+        let Self { initial, .. } = Self::empirical(moves, game)?;
+
+        let mut counts = vec![vec![0; game.shape_count()]; game.shape_count()];
+        moves
+            .windows(2)
+            .for_each(|pair| counts[pair[0].0][pair[1].0] += 1);
+
+        let transitions = counts
+            .into_iter()
+            .map(|row| counts_to_distribution(row).unwrap_or_else(|_| initial.clone()))
+            .collect();
+
+        Ok(Self::markov(initial, transitions))
+    }
+
+    /// Parses `uniform`, `empirical`, `markov`, `fixed:<weights>` or
+    /// `markov:<row>/<row>/...`, learning from `moves` where needed
+    pub fn parse(spec: &str, game: &Game, moves: &[Shape]) -> Result<Self, SimError> {
+        match spec.split_once(':') {
+            None if spec == "uniform" => Ok(Self::fixed(uniform(game))),
+            None if spec == "empirical" => Self::empirical(moves, game),
+            None if spec == "markov" => Self::empirical_markov(moves, game),
+            Some(("fixed", weights)) => Ok(Self::fixed(parse_distribution(weights, game)?)),
+            Some(("markov", rows)) => {
+                let rows = rows
+                    .split('/')
+                    .map(|row| parse_distribution(row, game))
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                if rows.len() != game.shape_count() {
+                    return Err(SimError::WrongRowCount {
+                        expected: game.shape_count(),
+                        found: rows.len(),
+                    });
+                }
+
+                Ok(Self::markov(uniform(game), rows))
+            }
+            _ => Err(SimError::UnknownSpec {
+                kind: "opponent",
+                spec: spec.to_owned(),
+            }),
+        }
+    }
+
+    fn distribution(&self, previous: Option<Shape>) -> &[f64] {
+        match previous {
+            None => &self.initial,
+            Some(shape) => &self.transitions[shape.0],
+        }
+    }
+}
+
+/// How we pick our moves, possibly reacting to the opponent's last move
+#[derive(Debug, Clone, PartialEq)]
+pub enum Policy {
+    Mixed(Distribution),
+    Reactive {
+        first: Distribution,
+        // after[a] is how we play after the opponent played a
+        after: Vec<Distribution>,
+    },
+}
+
+impl Policy {
+    /// Plays whatever beats the opponent's last move
+    pub fn beat_last(game: &Game) -> Self {
+        let after = game
+            .shapes()
+            .map(|last| {
+                let mut distribution = vec![0.0; game.shape_count()];
+                distribution[game.predict_move(last, Outcome::Win).0] = 1.0;
+                distribution
+            })
+            .collect();
+
+        Policy::Reactive {
+            first: uniform(game),
+            after,
+        }
+    }
+
+    /// Parses `uniform`, `beat-last`, `pure:<shape>` or `fixed:<weights>`
+    pub fn parse(spec: &str, game: &Game) -> Result<Self, SimError> {
+        let unknown = || SimError::UnknownSpec {
+            kind: "policy",
+            spec: spec.to_owned(),
+        };
+
+        match spec.split_once(':') {
+            None if spec == "uniform" => Ok(Policy::Mixed(uniform(game))),
+            None if spec == "beat-last" => Ok(Self::beat_last(game)),
+            Some(("pure", name)) => {
+                let shape = game.shape(name).ok_or_else(unknown)?;
+                let mut distribution = vec![0.0; game.shape_count()];
+                distribution[shape.0] = 1.0;
+                Ok(Policy::Mixed(distribution))
+            }
+            Some(("fixed", weights)) => Ok(Policy::Mixed(parse_distribution(weights, game)?)),
+            _ => Err(unknown()),
+        }
+    }
+
+    fn distribution(&self, opponent_previous: Option<Shape>) -> &[f64] {
+        match (self, opponent_previous) {
+            (Policy::Mixed(distribution), _) => distribution,
+            (Policy::Reactive { first, .. }, None) => first,
+            (Policy::Reactive { after, .. }, Some(shape)) => &after[shape.0],
+        }
+    }
+}
+
+/// How often each shape appears in `moves`
+pub fn frequencies(moves: &[Shape], game: &Game) -> Result<Distribution, SimError> {
+    let mut counts = vec![0; game.shape_count()];
+    moves.iter().for_each(|x| counts[x.0] += 1);
+
+    counts_to_distribution(counts)
+}
+
+pub fn uniform(game: &Game) -> Distribution {
+    vec![1.0 / game.shape_count() as f64; game.shape_count()]
+}
+
+/// The expected score of a single round, given what the opponent played last
+fn expected_round(
+    game: &Game,
+    opponent: &Opponent,
+    policy: &Policy,
+    previous: Option<Shape>,
+) -> f64 {
+    let theirs = opponent.distribution(previous);
+    let ours = policy.distribution(previous);
+
+    game.shapes()
+        .flat_map(|me| game.shapes().map(move |them| (me, them)))
+        .map(|(me, them)| ours[me.0] * theirs[them.0] * game.score(me, them) as f64)
+        .sum()
+}
+
+/// The exact expected total score over `rounds` rounds.
+///
+/// Both the opponent and our policy only ever look at the opponent's
+/// previous move, so tracking the distribution of that move is enough.
+pub fn expected_score(game: &Game, opponent: &Opponent, policy: &Policy, rounds: usize) -> f64 {
+    if rounds == 0 {
+        return 0.0;
+    }
+
+    let mut total = expected_round(game, opponent, policy, None);
+    let mut previous = opponent.initial.clone();
+
+    for _ in 1..rounds {
+        total += game
+            .shapes()
+            .map(|last| previous[last.0] * expected_round(game, opponent, policy, Some(last)))
+            .sum::<f64>();
+
+        previous = game
+            .shapes()
+            .map(|next| {
+                game.shapes()
+                    .map(|last| previous[last.0] * opponent.transitions[last.0][next.0])
+                    .sum()
+            })
+            .collect();
+    }
+
+    total
+}
+
+/// A Monte Carlo estimate of the total score, with a 95% confidence
+/// interval for the mean
+#[derive(Debug, Clone, Copy)]
+pub struct Estimate {
+    pub mean: f64,
+    pub std_dev: f64,
+    pub low: f64,
+    pub high: f64,
+}
+
+fn sample(distribution: &[f64], rng: &mut impl Rng) -> Shape {
+    let mut left: f64 = rng.gen();
+    for (i, chance) in distribution.iter().enumerate() {
+        if left < *chance {
+            return Shape(i);
+        }
+        left -= chance;
+    }
+
+    // Rounding can leave the chances summing to a little under 1
+    Shape(distribution.iter().rposition(|x| *x > 0.0).unwrap_or(0))
+}
+
+/// Plays `trials` games of `rounds` rounds each, and estimates the mean
+/// total score
+pub fn monte_carlo(
+    game: &Game,
+    opponent: &Opponent,
+    policy: &Policy,
+    rounds: usize,
+    trials: usize,
+    rng: &mut impl Rng,
+) -> Estimate {
+    let totals: Vec<f64> = (0..trials)
+        .map(|_| {
+            let mut previous = None;
+            let mut total = 0;
+
+            for _ in 0..rounds {
+                let them = sample(opponent.distribution(previous), rng);
+                let me = sample(policy.distribution(previous), rng);
+                total += game.score(me, them);
+                previous = Some(them);
+            }
+
+            total as f64
+        })
+        .collect();
+
+    let n = totals.len().max(1) as f64;
+    let mean = totals.iter().sum::<f64>() / n;
+    let variance = totals.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (n - 1.0).max(1.0);
+    let std_dev = variance.sqrt();
+    let margin = 1.96 * std_dev / n.sqrt();
+
+    Estimate {
+        mean,
+        std_dev,
+        low: mean - margin,
+        high: mean + margin,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn uniform_play_scores_the_average() {
+        let game = Game::rps();
+        let opponent = Opponent::fixed(uniform(&game));
+        let policy = Policy::Mixed(uniform(&game));
+
+        // 2 points for the average shape, and 3 for the average outcome
+        assert!((expected_score(&game, &opponent, &policy, 10) - 50.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn beat_last_exploits_a_sticky_opponent() {
+        let game = Game::rps();
+        let sticky = (0..3)
+            .map(|i| (0..3).map(|j| if i == j { 1.0 } else { 0.0 }).collect())
+            .collect();
+        let opponent = Opponent::markov(uniform(&game), sticky);
+
+        // After the first round, we always win
+        let expected = expected_score(&game, &opponent, &Policy::beat_last(&game), 3);
+        let first = 5.0;
+        let rest: f64 = game
+            .shapes()
+            .map(|them| game.score(game.predict_move(them, Outcome::Win), them) as f64 / 3.0)
+            .sum();
+        assert!((expected - (first + 2.0 * rest)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn monte_carlo_agrees_with_exact() {
+        let game = Game::rps();
+        let opponent = Opponent::fixed(vec![0.5, 0.3, 0.2]);
+        let policy = Policy::parse("fixed:1,2,1", &game).unwrap();
+
+        let exact = expected_score(&game, &opponent, &policy, 20);
+        let estimate = monte_carlo(
+            &game,
+            &opponent,
+            &policy,
+            20,
+            5000,
+            &mut StdRng::seed_from_u64(2022),
+        );
+
+        // Allow a few standard errors of slack, so the test doesn't hinge
+        // on the seed landing inside the 95% interval
+        let standard_error = estimate.std_dev / (5000f64).sqrt();
+        assert!((estimate.mean - exact).abs() < 4.0 * standard_error);
+    }
+
+    #[test]
+    fn rejects_bad_distributions() {
+        let game = Game::rps();
+        assert_eq!(
+            parse_distribution("1,2", &game),
+            Err(SimError::WrongLength {
+                expected: 3,
+                found: 2
+            })
+        );
+        assert_eq!(parse_distribution("0,0,0", &game), Err(SimError::AllZero));
+        assert_eq!(parse_distribution("1,-1,1", &game), Err(SimError::Negative));
+        assert_eq!(
+            parse_distribution("inf,1,1", &game),
+            Err(SimError::NotFinite)
+        );
+        assert_eq!(
+            parse_distribution("NaN,1,1", &game),
+            Err(SimError::NotFinite)
+        );
+        assert_eq!(
+            parse_distribution("1e308,1e308,1", &game),
+            Err(SimError::Overflow)
+        );
+    }
+}
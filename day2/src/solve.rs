@@ -0,0 +1,217 @@
+use crate::{
+    game::{Game, Shape},
+    sim::Distribution,
+};
+
+const EPSILON: f64 = 1e-9;
+
+/// What we are trying to maximise, and what the opponent tries to spoil
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Objective {
+    /// Our own score, against an opponent who wants to keep it low
+    Score,
+    /// Our score minus the opponent's, with both players after the lead
+    Margin,
+}
+
+impl Objective {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "score" => Some(Objective::Score),
+            "margin" => Some(Objective::Margin),
+            _ => None,
+        }
+    }
+}
+
+/// Our payoff for each pair of moves, indexed by `[ours][theirs]`
+pub fn payoff_matrix(game: &Game, objective: Objective) -> Vec<Vec<f64>> {
+    game.shapes()
+        .map(|me| {
+            game.shapes()
+                .map(|them| match objective {
+                    Objective::Score => game.score(me, them) as f64,
+                    Objective::Margin => (game.score(me, them) - game.score(them, me)) as f64,
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// A pair of mixed strategies neither player can improve on alone
+#[derive(Debug, Clone, PartialEq)]
+pub struct Equilibrium {
+    pub ours: Distribution,
+    pub theirs: Distribution,
+    /// Our expected payoff per round when both sides play their strategy
+    pub value: f64,
+}
+
+/// Solves the zero-sum game with the given payoff matrix.
+///
+/// The payoffs are shifted so they are all positive, which lets the column
+/// player's problem be written as the linear program
+/// `max Σw subject to Aw ≤ 1, w ≥ 0`. Its optimum is `1 / value`, the
+/// column player's strategy is `w` scaled to sum to 1, and the row player's
+/// strategy is read off the dual values of the constraints.
+pub fn solve_zero_sum(payoffs: &[Vec<f64>]) -> Equilibrium {
+    let rows = payoffs.len();
+    let cols = payoffs.first().map_or(0, Vec::len);
+
+    let min = payoffs
+        .iter()
+        .flatten()
+        .copied()
+        .fold(f64::INFINITY, f64::min);
+    let shift = 1.0 - min;
+
+    // Each tableau row is [w_0 .. w_cols, s_0 .. s_rows, rhs], with the
+    // objective row last
+    let width = cols + rows + 1;
+    let mut tableau: Vec<Vec<f64>> = payoffs
+        .iter()
+        .enumerate()
+        .map(|(i, row)| {
+            let mut line = vec![0.0; width];
+            line[..cols]
+                .iter_mut()
+                .zip(row)
+                .for_each(|(x, payoff)| *x = payoff + shift);
+            line[cols + i] = 1.0;
+            line[width - 1] = 1.0;
+            line
+        })
+        .collect();
+
+    let mut objective = vec![0.0; width];
+    objective[..cols].iter_mut().for_each(|x| *x = -1.0);
+    tableau.push(objective);
+
+    let mut basis: Vec<usize> = (cols..cols + rows).collect();
+
+    // Bland's rule: always take the lowest improving column, which
+    // guarantees the simplex method can't cycle
+    while let Some(pivot_col) = (0..width - 1).find(|&j| tableau[rows][j] < -EPSILON) {
+        let pivot_row = (0..rows)
+            .filter(|&i| tableau[i][pivot_col] > EPSILON)
+            .min_by(|&a, &b| {
+                let ratio = |i: usize| tableau[i][width - 1] / tableau[i][pivot_col];
+                ratio(a).total_cmp(&ratio(b)).then(basis[a].cmp(&basis[b]))
+            })
+            .expect("every payoff is positive, so the program is bounded");
+
+        let pivot = tableau[pivot_row][pivot_col];
+        tableau[pivot_row].iter_mut().for_each(|x| *x /= pivot);
+
+        let pivot_line = tableau[pivot_row].clone();
+        for (i, line) in tableau.iter_mut().enumerate() {
+            if i == pivot_row {
+                continue;
+            }
+
+            let factor = line[pivot_col];
+            line.iter_mut()
+                .zip(&pivot_line)
+                .for_each(|(x, p)| *x -= factor * p);
+        }
+
+        basis[pivot_row] = pivot_col;
+    }
+
+    let total = tableau[rows][width - 1];
+
+    let mut theirs = vec![0.0; cols];
+    for (i, &var) in basis.iter().enumerate() {
+        if var < cols {
+            theirs[var] = tableau[i][width - 1] / total;
+        }
+    }
+
+    let ours = (0..rows).map(|i| tableau[rows][cols + i] / total).collect();
+
+    Equilibrium {
+        ours,
+        theirs,
+        value: 1.0 / total - shift,
+    }
+}
+
+/// The best pure reply to an opponent playing `theirs`
+#[derive(Debug, Clone, PartialEq)]
+pub struct BestResponse {
+    pub shape: Shape,
+    /// The expected payoff of each of our shapes against `theirs`
+    pub payoffs: Vec<f64>,
+}
+
+pub fn best_response(payoffs: &[Vec<f64>], theirs: &[f64]) -> BestResponse {
+    let payoffs: Vec<f64> = payoffs
+        .iter()
+        .map(|row| row.iter().zip(theirs).map(|(a, p)| a * p).sum())
+        .collect();
+
+    let shape = payoffs
+        .iter()
+        .enumerate()
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(i, _)| Shape(i))
+        .expect("the game has at least one shape");
+
+    BestResponse { shape, payoffs }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-6, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn matching_pennies() {
+        let eq = solve_zero_sum(&[vec![1.0, -1.0], vec![-1.0, 1.0]]);
+        assert_close(eq.value, 0.0);
+        eq.ours.iter().for_each(|x| assert_close(*x, 0.5));
+        eq.theirs.iter().for_each(|x| assert_close(*x, 0.5));
+    }
+
+    #[test]
+    fn equilibrium_cant_be_exploited() {
+        for game in [Game::rps(), Game::rpsls()] {
+            for objective in [Objective::Score, Objective::Margin] {
+                let payoffs = payoff_matrix(&game, objective);
+                let eq = solve_zero_sum(&payoffs);
+
+                assert_close(eq.ours.iter().sum(), 1.0);
+                assert_close(eq.theirs.iter().sum(), 1.0);
+
+                // No reply does better than the value against their
+                // strategy, and no reply holds ours below it
+                let best = best_response(&payoffs, &eq.theirs);
+                assert_close(best.payoffs[best.shape.0], eq.value);
+                for them in game.shapes() {
+                    let payoff: f64 = game
+                        .shapes()
+                        .map(|me| eq.ours[me.0] * payoffs[me.0][them.0])
+                        .sum();
+                    assert!(payoff >= eq.value - 1e-6);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn margin_game_is_fair() {
+        // Swapping seats negates the margin, so neither side has an edge
+        let eq = solve_zero_sum(&payoff_matrix(&Game::rps(), Objective::Margin));
+        assert_close(eq.value, 0.0);
+    }
+
+    #[test]
+    fn best_response_to_rock() {
+        let game = Game::rps();
+        let best = best_response(&payoff_matrix(&game, Objective::Score), &[1.0, 0.0, 0.0]);
+        assert_eq!(best.shape, game.shape("paper").unwrap());
+    }
+}
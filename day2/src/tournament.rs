@@ -0,0 +1,276 @@
+use std::cmp::{Ordering, Reverse};
+
+use crate::{
+    game::{Game, Shape},
+    Outcome,
+};
+
+/// A way of picking moves, given what has happened so far in a match
+pub trait Strategy {
+    fn name(&self, game: &Game) -> String;
+
+    /// Gets ready for a new match, forgetting anything from the last one
+    fn start(&mut self, _game: &Game) {}
+
+    /// Picks the next move. `history` holds `(mine, theirs)` for every
+    /// round played so far in this match.
+    fn next_move(&mut self, game: &Game, history: &[(Shape, Shape)]) -> Shape;
+}
+
+/// Always plays the same shape
+pub struct Always(pub Shape);
+
+/// Plays every shape in turn
+pub struct Cycle;
+
+/// Plays whatever beats the opponent's last move
+pub struct BeatLast;
+
+/// Plays whatever beats the opponent's most common move
+#[derive(Default)]
+pub struct FrequencyCounter {
+    /// How often the opponent has played each shape in the first `seen`
+    /// rounds of this match
+    counts: Vec<usize>,
+    seen: usize,
+}
+
+/// Plays our moves from the strategy guide, starting over when it runs out
+pub struct FollowGuide(pub Vec<Shape>);
+
+impl Strategy for Always {
+    fn name(&self, game: &Game) -> String {
+        format!("always {}", game.name(self.0))
+    }
+
+    fn next_move(&mut self, _: &Game, _: &[(Shape, Shape)]) -> Shape {
+        self.0
+    }
+}
+
+impl Strategy for Cycle {
+    fn name(&self, _: &Game) -> String {
+        "cycle".to_owned()
+    }
+
+    fn next_move(&mut self, game: &Game, history: &[(Shape, Shape)]) -> Shape {
+        Shape(history.len() % game.shape_count())
+    }
+}
+
+impl Strategy for BeatLast {
+    fn name(&self, _: &Game) -> String {
+        "beat last move".to_owned()
+    }
+
+    fn next_move(&mut self, game: &Game, history: &[(Shape, Shape)]) -> Shape {
+        match history.last() {
+            Some(&(_, theirs)) => game.predict_move(theirs, Outcome::Win),
+            None => Shape(0),
+        }
+    }
+}
+
+impl Strategy for FrequencyCounter {
+    fn name(&self, _: &Game) -> String {
+        "frequency counter".to_owned()
+    }
+
+    fn start(&mut self, game: &Game) {
+        self.counts = vec![0; game.shape_count()];
+        self.seen = 0;
+    }
+
+    fn next_move(&mut self, game: &Game, history: &[(Shape, Shape)]) -> Shape {
+        // A history shorter than the one already counted belongs to a new
+        // match, which may have begun without `start`
+        if history.len() < self.seen || self.counts.len() != game.shape_count() {
+            self.start(game);
+        }
+        for (_, theirs) in &history[self.seen..] {
+            self.counts[theirs.0] += 1;
+        }
+        self.seen = history.len();
+
+        // Ties go to the first shape, so the first move is predictable
+        let favourite = game
+            .shapes()
+            .max_by_key(|x| (self.counts[x.0], Reverse(x.0)))
+            .expect("the game has at least one shape");
+
+        game.predict_move(favourite, Outcome::Win)
+    }
+}
+
+impl Strategy for FollowGuide {
+    fn name(&self, _: &Game) -> String {
+        "follow the guide".to_owned()
+    }
+
+    fn next_move(&mut self, _: &Game, history: &[(Shape, Shape)]) -> Shape {
+        self.0[history.len() % self.0.len()]
+    }
+}
+
+/// The total score of each side after a match of `rounds` rounds
+pub fn play_match(
+    game: &Game,
+    a: &mut dyn Strategy,
+    b: &mut dyn Strategy,
+    rounds: usize,
+) -> (isize, isize) {
+    a.start(game);
+    b.start(game);
+
+    let mut history_a = Vec::with_capacity(rounds);
+    let mut history_b = Vec::with_capacity(rounds);
+    let (mut score_a, mut score_b) = (0, 0);
+
+    for _ in 0..rounds {
+        let move_a = a.next_move(game, &history_a);
+        let move_b = b.next_move(game, &history_b);
+
+        score_a += game.score(move_a, move_b);
+        score_b += game.score(move_b, move_a);
+
+        history_a.push((move_a, move_b));
+        history_b.push((move_b, move_a));
+    }
+
+    (score_a, score_b)
+}
+
+/// A strategy's record across the tournament. A match win is worth 3
+/// league points and a draw 1.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Standing {
+    pub strategy: usize,
+    pub won: usize,
+    pub drawn: usize,
+    pub lost: usize,
+    pub score_for: isize,
+    pub score_against: isize,
+}
+
+impl Standing {
+    pub fn points(&self) -> usize {
+        self.won * 3 + self.drawn
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tournament {
+    /// `head_to_head[a][b]` is what `a` scored against `b`
+    pub head_to_head: Vec<Vec<Option<isize>>>,
+    /// Ranked by league points, then score difference, then score
+    pub standings: Vec<Standing>,
+}
+
+/// Plays every strategy against every other strategy once
+pub fn round_robin(game: &Game, strategies: &mut [Box<dyn Strategy>], rounds: usize) -> Tournament {
+    let n = strategies.len();
+    let mut head_to_head = vec![vec![None; n]; n];
+    let mut standings: Vec<_> = (0..n)
+        .map(|strategy| Standing {
+            strategy,
+            ..Default::default()
+        })
+        .collect();
+
+    for a in 0..n {
+        let (left, right) = strategies.split_at_mut(a + 1);
+        for (b, other) in right.iter_mut().enumerate().map(|(i, x)| (a + 1 + i, x)) {
+            let (score_a, score_b) = play_match(game, left[a].as_mut(), other.as_mut(), rounds);
+            head_to_head[a][b] = Some(score_a);
+            head_to_head[b][a] = Some(score_b);
+
+            for (me, mine, theirs) in [(a, score_a, score_b), (b, score_b, score_a)] {
+                let standing = &mut standings[me];
+                standing.score_for += mine;
+                standing.score_against += theirs;
+                match mine.cmp(&theirs) {
+                    Ordering::Greater => standing.won += 1,
+                    Ordering::Equal => standing.drawn += 1,
+                    Ordering::Less => standing.lost += 1,
+                }
+            }
+        }
+    }
+
+    standings.sort_by_key(|x| {
+        (
+            Reverse(x.points()),
+            Reverse(x.score_for - x.score_against),
+            Reverse(x.score_for),
+            x.strategy,
+        )
+    });
+
+    Tournament {
+        head_to_head,
+        standings,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn beat_last_punishes_always() {
+        let game = Game::rps();
+        let rock = game.shape("rock").unwrap();
+
+        // After the first round, beat last always plays paper
+        let (always, beat_last) = play_match(&game, &mut Always(rock), &mut BeatLast, 10);
+        assert_eq!(always, 4 + 9);
+        assert_eq!(beat_last, 4 + 9 * 8);
+    }
+
+    #[test]
+    fn league_table_is_ranked() {
+        let game = Game::rps();
+        let mut strategies: Vec<Box<dyn Strategy>> = vec![
+            Box::new(Always(Shape(0))),
+            Box::new(Cycle),
+            Box::new(BeatLast),
+            Box::<FrequencyCounter>::default(),
+        ];
+
+        let tournament = round_robin(&game, &mut strategies, 100);
+        let points: Vec<_> = tournament.standings.iter().map(|x| x.points()).collect();
+        assert!(points.windows(2).all(|x| x[0] >= x[1]));
+
+        for standing in &tournament.standings {
+            assert_eq!(standing.won + standing.drawn + standing.lost, 3);
+        }
+    }
+
+    #[test]
+    fn frequency_counter_forgets_between_matches() {
+        let game = Game::rps();
+        let [rock, paper, scissors] = ["rock", "paper", "scissors"].map(|x| game.shape(x).unwrap());
+        let mut counter = FrequencyCounter::default();
+
+        // Against rock it settles on paper, and scores the same both times
+        let first = play_match(&game, &mut counter, &mut Always(rock), 10);
+        assert_eq!(
+            first,
+            play_match(&game, &mut counter, &mut Always(rock), 10)
+        );
+        assert_eq!(counter.next_move(&game, &[(paper, rock)]), paper);
+
+        counter.start(&game);
+        let history = [(paper, scissors), (rock, scissors), (rock, rock)];
+        let moves: Vec<_> = (0..=history.len())
+            .map(|n| counter.next_move(&game, &history[..n]))
+            .collect();
+        assert_eq!(moves, [paper, rock, rock, rock]);
+
+        // Skipping rounds, or never calling `start`, still counts every one
+        let mut counter = FrequencyCounter::default();
+        assert_eq!(counter.next_move(&game, &history[..1]), rock);
+        assert_eq!(counter.next_move(&game, &history), rock);
+        assert_eq!(counter.next_move(&game, &[]), paper);
+    }
+}
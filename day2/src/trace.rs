@@ -0,0 +1,238 @@
+use std::{
+    borrow::Cow,
+    io::{self, Write},
+};
+
+use crate::{
+    game::{Game, Shape},
+    Outcome,
+};
+
+/// Where the points of a single round came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Step {
+    /// The round number, counting from 1
+    pub round: usize,
+    pub opponent: Shape,
+    pub me: Shape,
+    pub outcome: Outcome,
+    pub shape_points: isize,
+    pub outcome_points: isize,
+    /// The score after this round
+    pub total: isize,
+}
+
+pub fn trace(guide: &[(Shape, Shape)], game: &Game) -> Vec<Step> {
+    let mut total = 0;
+
+    guide
+        .iter()
+        .enumerate()
+        .map(|(i, &(opponent, me))| {
+            let outcome = game.outcome(me, opponent);
+            let shape_points = game.score_shape(me);
+            let outcome_points = outcome.score();
+            total += shape_points + outcome_points;
+
+            Step {
+                round: i + 1,
+                opponent,
+                me,
+                outcome,
+                shape_points,
+                outcome_points,
+                total,
+            }
+        })
+        .collect()
+}
+
+/// The points earned while playing a particular shape
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ShapeSummary {
+    pub plays: usize,
+    pub shape_points: isize,
+    pub outcome_points: isize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Summary {
+    pub rounds: usize,
+    pub wins: usize,
+    pub draws: usize,
+    pub losses: usize,
+    pub total: isize,
+    /// Indexed by our shape
+    pub per_shape: Vec<ShapeSummary>,
+}
+
+pub fn summarise(steps: &[Step], game: &Game) -> Summary {
+    let mut summary = Summary {
+        rounds: steps.len(),
+        wins: 0,
+        draws: 0,
+        losses: 0,
+        total: steps.last().map_or(0, |x| x.total),
+        per_shape: vec![ShapeSummary::default(); game.shape_count()],
+    };
+
+    for step in steps {
+        match step.outcome {
+            Outcome::Win => summary.wins += 1,
+            Outcome::Draw => summary.draws += 1,
+            Outcome::Lose => summary.losses += 1,
+        }
+
+        let shape = &mut summary.per_shape[step.me.0];
+        shape.plays += 1;
+        shape.shape_points += step.shape_points;
+        shape.outcome_points += step.outcome_points;
+    }
+
+    summary
+}
+
+pub fn write_table(out: &mut impl Write, steps: &[Step], game: &Game) -> io::Result<()> {
+    writeln!(
+        out,
+        "{:>6} {:>10} {:>10} {:>8} {:>6} {:>8} {:>8}",
+        "round", "opponent", "me", "outcome", "shape", "outcome", "total"
+    )?;
+
+    for step in steps {
+        writeln!(
+            out,
+            "{:>6} {:>10} {:>10} {:>8} {:>6} {:>8} {:>8}",
+            step.round,
+            game.name(step.opponent),
+            game.name(step.me),
+            step.outcome.to_string(),
+            step.shape_points,
+            step.outcome_points,
+            step.total
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Quotes a CSV field if it holds a separator, quote or line break,
+/// doubling any quotes inside as RFC 4180 asks
+fn csv_field(field: &str) -> Cow<'_, str> {
+    match field.contains([',', '"', '\r', '\n']) {
+        true => Cow::Owned(format!("\"{}\"", field.replace('"', "\"\""))),
+        false => Cow::Borrowed(field),
+    }
+}
+
+pub fn write_csv(out: &mut impl Write, steps: &[Step], game: &Game) -> io::Result<()> {
+    writeln!(
+        out,
+        "round,opponent,me,outcome,shape_points,outcome_points,total"
+    )?;
+
+    for step in steps {
+        writeln!(
+            out,
+            "{},{},{},{},{},{},{}",
+            step.round,
+            csv_field(game.name(step.opponent)),
+            csv_field(game.name(step.me)),
+            step.outcome,
+            step.shape_points,
+            step.outcome_points,
+            step.total
+        )?;
+    }
+
+    Ok(())
+}
+
+pub fn write_summary(out: &mut impl Write, summary: &Summary, game: &Game) -> io::Result<()> {
+    let rate = |count: usize| 100.0 * count as f64 / summary.rounds.max(1) as f64;
+
+    writeln!(out, "{} rounds, {} points", summary.rounds, summary.total)?;
+    writeln!(
+        out,
+        "won {} ({:.1}%), drew {} ({:.1}%), lost {} ({:.1}%)",
+        summary.wins,
+        rate(summary.wins),
+        summary.draws,
+        rate(summary.draws),
+        summary.losses,
+        rate(summary.losses)
+    )?;
+
+    writeln!(out)?;
+    writeln!(
+        out,
+        "{:>10} {:>6} {:>8} {:>8} {:>8}",
+        "shape", "plays", "shape", "outcome", "total"
+    )?;
+    for shape in game.shapes() {
+        let x = summary.per_shape[shape.0];
+        writeln!(
+            out,
+            "{:>10} {:>6} {:>8} {:>8} {:>8}",
+            game.name(shape),
+            x.plays,
+            x.shape_points,
+            x.outcome_points,
+            x.shape_points + x.outcome_points
+        )?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn breakdown_adds_up() {
+        let game = Game::rps();
+        let [rock, paper, scissors] = ["rock", "paper", "scissors"].map(|x| game.shape(x).unwrap());
+        let guide = [(rock, paper), (paper, rock), (scissors, scissors)];
+
+        let steps = trace(&guide, &game);
+        assert_eq!(
+            steps.iter().map(|x| x.total).collect::<Vec<_>>(),
+            [8, 9, 15]
+        );
+
+        let summary = summarise(&steps, &game);
+        assert_eq!((summary.wins, summary.draws, summary.losses), (1, 1, 1));
+        assert_eq!(summary.total, 15);
+        assert_eq!(
+            summary
+                .per_shape
+                .iter()
+                .map(|x| x.shape_points + x.outcome_points)
+                .sum::<isize>(),
+            summary.total
+        );
+    }
+
+    #[test]
+    fn csv_quotes_awkward_names() {
+        let game: Game = "a,b=1 \"c\"=2 d=3\na,b beats d\n\"c\" beats a,b\nd beats \"c\""
+            .parse()
+            .unwrap();
+        let [ab, c] = ["a,b", "\"c\""].map(|x| game.shape(x).unwrap());
+
+        let mut out = Vec::new();
+        write_csv(&mut out, &trace(&[(ab, c)], &game), &game).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap().lines().nth(1),
+            Some(r#"1,"a,b","""c""",win,2,6,8"#)
+        );
+
+        // The table isn't CSV, so it shows the names as they are
+        let mut out = Vec::new();
+        write_table(&mut out, &trace(&[(ab, c)], &game), &game).unwrap();
+        let table = String::from_utf8(out).unwrap();
+        let row: Vec<_> = table.lines().nth(1).unwrap().split_whitespace().collect();
+        assert_eq!(row[1..3], ["a,b", "\"c\""]);
+    }
+}
@@ -0,0 +1,184 @@
+use bitset::BitSet;
+use std::collections::HashMap;
+use thiserror::Error;
+
+/// The items that may appear in a rucksack, and the priority of each.
+///
+/// Items are identified by their position in the alphabet, so sets of
+/// items can be stored as bitsets no matter which characters are used.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Alphabet {
+    items: Vec<char>,
+    priorities: Vec<isize>,
+    index: HashMap<char, usize>,
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum AlphabetError {
+    #[error("Item '{0}' appears in the alphabet more than once")]
+    Duplicate(char),
+    #[error(r#"Line {0}: expected "<item> <priority>""#)]
+    BadLine(usize),
+    #[error(r#"Line {line}: expected a single character item, but found "{found}""#)]
+    BadItem { line: usize, found: String },
+    #[error(r#"Line {line}: expected a priority, but found "{found}""#)]
+    BadPriority { line: usize, found: String },
+    #[error("The alphabet doesn't contain any items")]
+    Empty,
+}
+
+impl Alphabet {
+    /// The puzzle's alphabet: `a..=z` have priorities 1 to 26, and
+    /// `A..=Z` have priorities 27 to 52
+    pub fn standard() -> Self {
+        Self::from_fn(('a'..='z').chain('A'..='Z'), |c| match c {
+            'a'..='z' => c as isize - 'a' as isize + 1,
+            _ => c as isize - 'A' as isize + 27,
+        })
+        .unwrap()
+    }
+
+    /// Builds an alphabet of `items`, using `priority` to score each of them
+    pub fn from_fn(
+        items: impl IntoIterator<Item = char>,
+        priority: impl Fn(char) -> isize,
+    ) -> Result<Self, AlphabetError> {
+        let mut alphabet = Alphabet {
+            items: Vec::new(),
+            priorities: Vec::new(),
+            index: HashMap::new(),
+        };
+
+        for item in items {
+            if alphabet.index.insert(item, alphabet.items.len()).is_some() {
+                return Err(AlphabetError::Duplicate(item));
+            }
+            alphabet.items.push(item);
+            alphabet.priorities.push(priority(item));
+        }
+
+        if alphabet.items.is_empty() {
+            return Err(AlphabetError::Empty);
+        }
+
+        Ok(alphabet)
+    }
+
+    /// Reads an alphabet with one `<item> <priority>` pair per line.
+    /// Blank lines are ignored.
+    pub fn parse(s: &str) -> Result<Self, AlphabetError> {
+        let mut priorities = HashMap::new();
+        let mut items = Vec::new();
+
+        for (i, line) in s.lines().enumerate() {
+            let line_no = i + 1;
+            let mut words = line.split_whitespace();
+
+            let (item, priority) = match (words.next(), words.next(), words.next()) {
+                (None, _, _) => continue,
+                (Some(item), Some(priority), None) => (item, priority),
+                _ => return Err(AlphabetError::BadLine(line_no)),
+            };
+
+            let mut chars = item.chars();
+            let (Some(item), None) = (chars.next(), chars.next()) else {
+                return Err(AlphabetError::BadItem {
+                    line: line_no,
+                    found: item.to_owned(),
+                });
+            };
+
+            let priority = priority.parse().map_err(|_| AlphabetError::BadPriority {
+                line: line_no,
+                found: priority.to_owned(),
+            })?;
+
+            if priorities.insert(item, priority).is_some() {
+                return Err(AlphabetError::Duplicate(item));
+            }
+            items.push(item);
+        }
+
+        Self::from_fn(items, |c| priorities[&c])
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn index_of(&self, item: char) -> Option<usize> {
+        self.index.get(&item).copied()
+    }
+
+    pub fn item(&self, index: usize) -> char {
+        self.items[index]
+    }
+
+    pub fn priority(&self, index: usize) -> isize {
+        self.priorities[index]
+    }
+
+    /// An empty set with room for every item in the alphabet
+    pub fn item_set(&self) -> ItemSet {
+        BitSet::with_capacity(self.len())
+    }
+}
+
+/// A set of items from an [`Alphabet`], stored as one bit per item
+pub type ItemSet = BitSet;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitset::Bits;
+
+    #[test]
+    fn standard_priorities() {
+        let alphabet = Alphabet::standard();
+        let priority = |c| alphabet.priority(alphabet.index_of(c).unwrap());
+
+        assert_eq!(priority('a'), 1);
+        assert_eq!(priority('z'), 26);
+        assert_eq!(priority('A'), 27);
+        assert_eq!(priority('Z'), 52);
+        assert_eq!(alphabet.index_of('é'), None);
+    }
+
+    #[test]
+    fn parses_unicode_alphabets() {
+        let alphabet = Alphabet::parse("é 5\n\n🎒 100\n").unwrap();
+        assert_eq!(alphabet.len(), 2);
+        assert_eq!(alphabet.priority(alphabet.index_of('🎒').unwrap()), 100);
+
+        assert_eq!(
+            Alphabet::parse("a 1\na 2"),
+            Err(AlphabetError::Duplicate('a'))
+        );
+        assert_eq!(
+            Alphabet::parse("ab 1"),
+            Err(AlphabetError::BadItem {
+                line: 1,
+                found: "ab".to_owned()
+            })
+        );
+    }
+
+    #[test]
+    fn sets_scale_past_128_items() {
+        let alphabet = Alphabet::from_fn((0..300).filter_map(char::from_u32), |_| 1).unwrap();
+        let mut a = alphabet.item_set();
+        let mut b = alphabet.item_set();
+
+        a.insert(5);
+        a.insert(250);
+        b.insert(250);
+        b.insert(299);
+
+        b.insert(64);
+        assert_eq!(b.iter().collect::<Vec<_>>(), [64, 250, 299]);
+
+        a.intersect_with(&b);
+        assert_eq!(a.iter().collect::<Vec<_>>(), [250]);
+        assert!(!a.contains(5));
+    }
+}
@@ -0,0 +1,193 @@
+use std::fmt::Display;
+
+use crate::{alphabet::Alphabet, compartment_items, Rucksack, RucksackError};
+
+/// An item type found in both compartments of a rucksack
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Misplaced {
+    pub item: char,
+    pub priority: isize,
+    /// Where the item appears in the first compartment
+    pub left: Vec<usize>,
+    /// Where the item appears in the second compartment, counting from
+    /// the start of that compartment
+    pub right: Vec<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SackAudit {
+    pub line: usize,
+    /// Ordered by the item's position in the alphabet
+    pub misplaced: Vec<Misplaced>,
+}
+
+/// How often an item type was misplaced across the whole file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ItemTotal {
+    pub item: char,
+    pub priority: isize,
+    /// The rucksacks that have the item in both compartments
+    pub rucksacks: usize,
+    /// Every copy of the item in those rucksacks, in either compartment
+    pub copies: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Audit {
+    pub sacks: Vec<SackAudit>,
+    /// Only items that were misplaced at least once, in alphabet order
+    pub totals: Vec<ItemTotal>,
+}
+
+pub fn audit_sack(alphabet: &Alphabet, sack: &Rucksack) -> Result<SackAudit, RucksackError> {
+    let items = compartment_items(alphabet, sack)?;
+    let (left, right) = items.split_at(items.len() / 2);
+
+    let mut positions = vec![(Vec::new(), Vec::new()); alphabet.len()];
+    left.iter()
+        .enumerate()
+        .for_each(|(i, x)| positions[*x].0.push(i));
+    right
+        .iter()
+        .enumerate()
+        .for_each(|(i, x)| positions[*x].1.push(i));
+
+    let misplaced = positions
+        .into_iter()
+        .enumerate()
+        .filter(|(_, (left, right))| !left.is_empty() && !right.is_empty())
+        .map(|(index, (left, right))| Misplaced {
+            item: alphabet.item(index),
+            priority: alphabet.priority(index),
+            left,
+            right,
+        })
+        .collect();
+
+    Ok(SackAudit {
+        line: sack.line,
+        misplaced,
+    })
+}
+
+pub fn audit(alphabet: &Alphabet, sacks: &[Rucksack]) -> Result<Audit, RucksackError> {
+    let sacks = sacks
+        .iter()
+        .map(|sack| audit_sack(alphabet, sack))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut totals: Vec<_> = (0..alphabet.len())
+        .map(|index| ItemTotal {
+            item: alphabet.item(index),
+            priority: alphabet.priority(index),
+            rucksacks: 0,
+            copies: 0,
+        })
+        .collect();
+
+    for misplaced in sacks.iter().flat_map(|x| &x.misplaced) {
+        let total = &mut totals[alphabet.index_of(misplaced.item).unwrap()];
+        total.rucksacks += 1;
+        total.copies += misplaced.left.len() + misplaced.right.len();
+    }
+    totals.retain(|x| x.rucksacks > 0);
+
+    Ok(Audit { sacks, totals })
+}
+
+impl Display for Audit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let positions = |x: &[usize]| {
+            x.iter()
+                .map(|x| x.to_string())
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+
+        for sack in &self.sacks {
+            write!(f, "line {}:", sack.line)?;
+            for x in &sack.misplaced {
+                write!(
+                    f,
+                    " '{}' (priority {}, left {}, right {})",
+                    x.item,
+                    x.priority,
+                    positions(&x.left),
+                    positions(&x.right)
+                )?;
+            }
+            writeln!(f)?;
+        }
+
+        writeln!(f, "totals:")?;
+        for x in &self.totals {
+            writeln!(
+                f,
+                "'{}' (priority {}): misplaced in {} rucksacks, {} copies",
+                x.item, x.priority, x.rucksacks, x.copies
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rucksacks;
+
+    #[test]
+    fn finds_every_misplaced_item() {
+        let alphabet = Alphabet::standard();
+        let sacks = rucksacks("abaBcbBa\nxyyx", false).unwrap();
+        let audit = audit(&alphabet, &sacks).unwrap();
+
+        assert_eq!(
+            audit.sacks[0].misplaced,
+            [
+                Misplaced {
+                    item: 'a',
+                    priority: 1,
+                    left: vec![0, 2],
+                    right: vec![3]
+                },
+                Misplaced {
+                    item: 'b',
+                    priority: 2,
+                    left: vec![1],
+                    right: vec![1]
+                },
+                Misplaced {
+                    item: 'B',
+                    priority: 28,
+                    left: vec![3],
+                    right: vec![2]
+                },
+            ]
+        );
+        assert_eq!(
+            audit.totals[0],
+            ItemTotal {
+                item: 'a',
+                priority: 1,
+                rucksacks: 1,
+                copies: 3
+            }
+        );
+        assert_eq!(audit.totals.len(), 5);
+    }
+
+    #[test]
+    fn agrees_with_part1() {
+        let alphabet = Alphabet::standard();
+        let sacks = rucksacks(include_str!("input.txt"), false).unwrap();
+        let audit = audit(&alphabet, &sacks).unwrap();
+
+        let sum: isize = audit
+            .sacks
+            .iter()
+            .map(|x| x.misplaced.iter().map(|x| x.priority).sum::<isize>())
+            .sum();
+        assert_eq!(sum, 7831);
+    }
+}
@@ -0,0 +1,200 @@
+use std::fmt::Display;
+
+use bitset::Bits;
+
+use crate::{
+    alphabet::{Alphabet, ItemSet},
+    item_set, items, Rucksack, RucksackError,
+};
+
+/// How rucksacks are gathered into groups of elves
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Grouping {
+    /// Every `n` consecutive rucksacks form a group
+    Consecutive(usize),
+    /// Rucksacks with the same key form a group, whatever their order
+    ByKey,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Group<'a> {
+    /// The group's key, or its position when grouping consecutive lines
+    pub label: String,
+    pub sacks: Vec<&'a Rucksack<'a>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GroupProblem {
+    /// The last group ran out of rucksacks
+    Incomplete {
+        expected: usize,
+        found: usize,
+    },
+    NoBadge,
+    SeveralBadges(Vec<char>),
+}
+
+impl Display for GroupProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GroupProblem::Incomplete { expected, found } => {
+                write!(f, "expected {} rucksacks, but found {}", expected, found)
+            }
+            GroupProblem::NoBadge => write!(f, "no item is shared by every rucksack"),
+            GroupProblem::SeveralBadges(items) => {
+                let items: String = items.iter().collect();
+                write!(f, "several items are shared by every rucksack: {}", items)
+            }
+        }
+    }
+}
+
+/// What was found for a single group
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GroupReport {
+    pub label: String,
+    pub lines: Vec<usize>,
+    /// The badge and its priority, if the group has exactly one
+    pub badge: Result<(char, isize), GroupProblem>,
+}
+
+pub fn group<'a>(sacks: &'a [Rucksack<'a>], grouping: Grouping) -> Vec<Group<'a>> {
+    match grouping {
+        Grouping::Consecutive(size) => sacks
+            .chunks(size.max(1))
+            .enumerate()
+            .map(|(i, chunk)| Group {
+                label: format!("group {}", i + 1),
+                sacks: chunk.iter().collect(),
+            })
+            .collect(),
+
+        Grouping::ByKey => {
+            let mut groups: Vec<Group> = Vec::new();
+            for sack in sacks {
+                let key = sack.key.unwrap_or_default();
+                match groups.iter_mut().find(|x| x.label == key) {
+                    Some(group) => group.sacks.push(sack),
+                    None => groups.push(Group {
+                        label: key.to_owned(),
+                        sacks: vec![sack],
+                    }),
+                }
+            }
+            groups
+        }
+    }
+}
+
+/// Finds the badge of every group. Each group must share exactly one item,
+/// and when grouping consecutive lines every group must be full.
+pub fn find_badges(
+    groups: &[Group],
+    grouping: Grouping,
+    alphabet: &Alphabet,
+) -> Result<Vec<GroupReport>, RucksackError> {
+    groups
+        .iter()
+        .map(|group| {
+            let lines = group.sacks.iter().map(|x| x.line).collect();
+            let report = |badge| GroupReport {
+                label: group.label.clone(),
+                lines,
+                badge,
+            };
+
+            if let Grouping::Consecutive(size) = grouping {
+                if group.sacks.len() != size {
+                    return Ok(report(Err(GroupProblem::Incomplete {
+                        expected: size,
+                        found: group.sacks.len(),
+                    })));
+                }
+            }
+
+            let mut shared: Option<ItemSet> = None;
+            for sack in &group.sacks {
+                let set = item_set(alphabet, &items(alphabet, sack.line, sack.items)?);
+                match &mut shared {
+                    Some(shared) => shared.intersect_with(&set),
+                    None => shared = Some(set),
+                }
+            }
+
+            let badges: Vec<_> = shared.iter().flat_map(ItemSet::iter).collect();
+            Ok(report(match badges[..] {
+                [badge] => Ok((alphabet.item(badge), alphabet.priority(badge))),
+                [] => Err(GroupProblem::NoBadge),
+                _ => Err(GroupProblem::SeveralBadges(
+                    badges.into_iter().map(|x| alphabet.item(x)).collect(),
+                )),
+            }))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rucksacks;
+
+    const EXAMPLE: &str = "\
+vJrwpWtwJgWrhcsFMMfFFhFp
+jqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL
+PmmdzqPrVvPwwTWBwg
+wMqvLMZHhHMvwLHjbvcjnnSBnvTQFn
+ttgJtRGJQctTZtZT
+CrZsJsPPZsGzwwsLwLmpwMDw";
+
+    fn badges(input: &str, keyed: bool, grouping: Grouping) -> Vec<Result<char, GroupProblem>> {
+        let alphabet = Alphabet::standard();
+        let sacks = rucksacks(input, keyed).unwrap();
+        find_badges(&group(&sacks, grouping), grouping, &alphabet)
+            .unwrap()
+            .into_iter()
+            .map(|x| x.badge.map(|(badge, _)| badge))
+            .collect()
+    }
+
+    #[test]
+    fn finds_the_example_badges() {
+        assert_eq!(
+            badges(EXAMPLE, false, Grouping::Consecutive(3)),
+            [Ok('r'), Ok('Z')]
+        );
+    }
+
+    #[test]
+    fn reports_bad_groups() {
+        assert_eq!(
+            badges(EXAMPLE, false, Grouping::Consecutive(4)),
+            [
+                Err(GroupProblem::NoBadge),
+                Err(GroupProblem::Incomplete {
+                    expected: 4,
+                    found: 2
+                })
+            ]
+        );
+        assert_eq!(
+            badges("ab\nab\ncd", false, Grouping::Consecutive(2)),
+            [
+                Err(GroupProblem::SeveralBadges(vec!['a', 'b'])),
+                Err(GroupProblem::Incomplete {
+                    expected: 2,
+                    found: 1
+                })
+            ]
+        );
+        assert_eq!(
+            badges("ab\ncd", false, Grouping::Consecutive(2)),
+            [Err(GroupProblem::NoBadge)]
+        );
+    }
+
+    #[test]
+    fn groups_by_key() {
+        let input = "x abc\ny def\nx cxy\ny fgh\ny zzf";
+        assert_eq!(badges(input, true, Grouping::ByKey), [Ok('c'), Ok('f')]);
+    }
+}
@@ -1,57 +1,252 @@
+use anyhow::{anyhow, bail, Context, Result};
 use itertools::Itertools;
+use thiserror::Error;
 
-fn priority(c: u8) -> Option<isize> {
-    match c as char {
-        'a'..='z' => Some((c - 96) as isize),
-        'A'..='Z' => Some((c - 38) as isize),
-        _ => None,
-    }
-}
+use alphabet::{Alphabet, ItemSet};
+use bitset::Bits;
+use groups::{GroupReport, Grouping};
 
-fn part1(input: &str) {
-    assert!(input.is_ascii());
-
-    let sizes = input.lines().map(str::len);
-    let lines = input.lines().map(str::as_bytes);
-
-    let score = lines
-        .zip(sizes)
-        .map(|(line, size)| line.split_at(size / 2))
-        .fold(0isize, |score, (left, right)| {
-            let mut bitvec = 0u128;
-            left.iter().for_each(|x| bitvec |= 1 << x);
-            score
-                + right
-                    .iter()
-                    .find(|x| (bitvec & (1 << *x)) > 0)
-                    .copied()
-                    .and_then(priority)
-                    .unwrap()
-        });
+mod alphabet;
+mod audit;
+mod groups;
+mod repack;
 
-    println!("Score: {}", score);
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum RucksackError {
+    #[error("Line {line}: '{item}' isn't in the item alphabet")]
+    UnknownItem { line: usize, item: char },
+    #[error("Line {0}: a rucksack with an odd number of items can't be split in two")]
+    OddSize(usize),
+    #[error("Line {0}: no item is in both compartments")]
+    NoSharedItem(usize),
+    #[error(r#"Line {0}: expected "<key> <items>""#)]
+    MissingKey(usize),
 }
 
-fn part2(input: &str) {
-    assert!(input.is_ascii());
+/// A single line of the input
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rucksack<'a> {
+    pub line: usize,
+    /// The group key, when the input has a key column
+    pub key: Option<&'a str>,
+    pub items: &'a str,
+}
 
-    let sum: isize = input
+fn rucksacks(input: &str, keyed: bool) -> Result<Vec<Rucksack<'_>>, RucksackError> {
+    input
         .lines()
-        .map(str::as_bytes)
+        .enumerate()
+        .map(|(i, line)| {
+            let (key, items) = match keyed {
+                true => line
+                    .split_once(' ')
+                    .map(|(key, items)| (Some(key), items))
+                    .ok_or(RucksackError::MissingKey(i + 1))?,
+                false => (None, line),
+            };
+
+            Ok(Rucksack {
+                line: i + 1,
+                key,
+                items,
+            })
+        })
+        .collect()
+}
+
+/// Converts a rucksack's items into their indices in the `alphabet`
+fn items(alphabet: &Alphabet, line: usize, sack: &str) -> Result<Vec<usize>, RucksackError> {
+    sack.chars()
+        .map(|item| {
+            alphabet
+                .index_of(item)
+                .ok_or(RucksackError::UnknownItem { line, item })
+        })
+        .collect()
+}
+
+fn item_set(alphabet: &Alphabet, items: &[usize]) -> ItemSet {
+    let mut set = alphabet.item_set();
+    items.iter().for_each(|x| set.insert(*x));
+    set
+}
+
+/// Converts a rucksack's items into their indices in the `alphabet`,
+/// checking that they can be split into two equal compartments
+fn compartment_items(alphabet: &Alphabet, sack: &Rucksack) -> Result<Vec<usize>, RucksackError> {
+    let items = items(alphabet, sack.line, sack.items)?;
+    if items.len() % 2 != 0 {
+        return Err(RucksackError::OddSize(sack.line));
+    }
+    Ok(items)
+}
+
+/// The first item in the `right` compartment that's also in the `left` one
+fn shared_item(alphabet: &Alphabet, left: &[usize], right: &[usize]) -> Option<usize> {
+    let left = item_set(alphabet, left);
+    right.iter().copied().find(|x| left.contains(*x))
+}
+
+fn part1(sacks: &[Rucksack], alphabet: &Alphabet) -> Result<isize, RucksackError> {
+    sacks
+        .iter()
         .map(|sack| {
-            let mut bitvec = 0u128;
-            sack.iter().for_each(|x| bitvec |= 1 << x);
-            bitvec
+            let items = compartment_items(alphabet, sack)?;
+            let (left, right) = items.split_at(items.len() / 2);
+            shared_item(alphabet, left, right)
+                .map(|x| alphabet.priority(x))
+                .ok_or(RucksackError::NoSharedItem(sack.line))
         })
-        .batching(|x| x.take(3).reduce(|accum, bitvec| accum & bitvec))
-        .map(|x| priority(x.trailing_zeros() as u8))
-        .flatten()
+        .sum()
+}
+
+fn part2(
+    sacks: &[Rucksack],
+    grouping: Grouping,
+    alphabet: &Alphabet,
+) -> Result<Vec<GroupReport>, RucksackError> {
+    groups::find_badges(&groups::group(sacks, grouping), grouping, alphabet)
+}
+
+fn describe(report: &GroupReport) -> String {
+    let lines = match &report.lines[..] {
+        [line] => format!("line {}", line),
+        lines => format!("lines {}", lines.iter().join(", ")),
+    };
+
+    match &report.badge {
+        Ok((badge, priority)) => format!(
+            "{} ({}): badge '{}' with priority {}",
+            report.label, lines, badge, priority
+        ),
+        Err(problem) => format!("{} ({}): {}", report.label, lines, problem),
+    }
+}
+
+const USAGE: &str = "\
+usage: day3 [options]
+
+options:
+    --input <path>          read the rucksacks from a file instead of the puzzle input
+    --priorities <path>     read the item alphabet from a file, with one
+                            \"<item> <priority>\" pair per line
+    --group-size <n>        group every n consecutive rucksacks in part 2 (default 3)
+    --group-by-key          read lines as \"<key> <items>\", and group the
+                            rucksacks that share a key in part 2
+    --show-groups           print the badge found for every group
+    --audit                 list every item found in both compartments of each
+                            rucksack, with totals per item, instead of solving
+    --repack                plan the fewest swaps that leave no item type in
+                            both compartments, instead of solving";
+
+fn read_file(path: &str) -> Result<String> {
+    std::fs::read_to_string(path).with_context(|| format!("Couldn't read '{}'", path))
+}
+
+fn run(input: &str) -> Result<()> {
+    let mut alphabet = Alphabet::standard();
+    let mut rucksacks = None;
+    let mut group_size = 3;
+    let mut by_key = false;
+    let mut show_groups = false;
+    let mut audit = false;
+    let mut repack = false;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| anyhow!("Expected a value after '{}'", arg))
+        };
+
+        match arg.as_str() {
+            "--input" => rucksacks = Some(read_file(&value()?)?),
+            "--priorities" => {
+                alphabet = Alphabet::parse(&read_file(&value()?)?)
+                    .context("Failed to parse the item alphabet")?
+            }
+            "--group-size" => {
+                let size = value()?;
+                group_size = match size.parse() {
+                    Ok(0) | Err(_) => bail!("Expected a positive group size, but found '{}'", size),
+                    Ok(size) => size,
+                }
+            }
+            "--group-by-key" => by_key = true,
+            "--show-groups" => show_groups = true,
+            "--audit" => audit = true,
+            "--repack" => repack = true,
+            _ => bail!("{}", USAGE),
+        }
+    }
+
+    let input = rucksacks.as_deref().unwrap_or(input);
+    let sacks = self::rucksacks(input, by_key).context("Failed to read the rucksacks")?;
+    let grouping = match by_key {
+        true => Grouping::ByKey,
+        false => Grouping::Consecutive(group_size),
+    };
+
+    if audit {
+        let audit = audit::audit(&alphabet, &sacks).context("Failed to audit the rucksacks")?;
+        print!("{}", audit);
+        return Ok(());
+    }
+
+    if repack {
+        let (mut swaps, mut infeasible) = (0, 0);
+        for sack in &sacks {
+            let plan = repack::plan(&alphabet, sack).context("Failed to plan the repacking")?;
+            println!("{}", plan);
+
+            match &plan.swaps {
+                Some(plan) if !repack::verify(&alphabet, sack, plan)? => {
+                    bail!(
+                        "Line {}: the plan leaves items in both compartments",
+                        sack.line
+                    )
+                }
+                Some(plan) => swaps += plan.len(),
+                None => infeasible += 1,
+            }
+        }
+        println!(
+            "Total: {} swaps, {} infeasible rucksacks",
+            swaps, infeasible
+        );
+        return Ok(());
+    }
+
+    let score = part1(&sacks, &alphabet).context("Failed to complete part 1")?;
+    println!("Score: {}", score);
+
+    let reports = part2(&sacks, grouping, &alphabet).context("Failed to complete part 2")?;
+    if show_groups {
+        reports.iter().for_each(|x| println!("{}", describe(x)));
+    }
+
+    let bad: Vec<_> = reports.iter().filter(|x| x.badge.is_err()).collect();
+    if !bad.is_empty() {
+        let details = bad.iter().map(|x| format!("- {}", describe(x))).join("\n");
+        bail!(
+            "Failed to complete part 2: {} of {} groups don't have exactly one badge\n{}",
+            bad.len(),
+            reports.len(),
+            details
+        );
+    }
+
+    let sum: isize = reports
+        .iter()
+        .filter_map(|x| x.badge.as_ref().ok())
+        .map(|x| x.1)
         .sum();
+    println!("Sum: {}", sum);
 
-    println!("Sum: {}", sum)
+    Ok(())
 }
 
-fn main() {
+fn main() -> Result<()> {
     let input = include_str!("input.txt");
     // let input = r#"vJrwpWtwJgWrhcsFMMfFFhFp
     // jqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL
@@ -60,6 +255,5 @@ fn main() {
     // ttgJtRGJQctTZtZT
     // CrZsJsPPZsGzwwsLwLmpwMDw"#;
 
-    part1(input);
-    part2(input);
+    run(input)
 }
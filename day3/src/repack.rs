@@ -0,0 +1,190 @@
+use std::fmt::Display;
+
+use crate::{alphabet::Alphabet, compartment_items, shared_item, Rucksack, RucksackError};
+
+/// Exchanges the item at `left` in the first compartment with the item at
+/// `right` in the second, counting each position from the start of its
+/// compartment
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Swap {
+    pub left: usize,
+    pub right: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SackPlan {
+    pub line: usize,
+    /// The fewest swaps that leave no item type in both compartments, or
+    /// `None` if the items can't be split into two equal halves that way
+    pub swaps: Option<Vec<Swap>>,
+}
+
+/// Finds the fewest swaps that separate every item type into a single
+/// compartment.
+///
+/// Since both compartments keep their size, every item moved out of one
+/// compartment is matched by an item moved into it, so moves always pair
+/// up into swaps. Each item type is sent wholly to one side, and a
+/// subset-sum over the types picks the sides that fill the first
+/// compartment exactly while moving the fewest items.
+pub fn plan(alphabet: &Alphabet, sack: &Rucksack) -> Result<SackPlan, RucksackError> {
+    let items = compartment_items(alphabet, sack)?;
+    let half = items.len() / 2;
+    let (left, right) = items.split_at(half);
+
+    let mut positions = vec![(Vec::new(), Vec::new()); alphabet.len()];
+    left.iter()
+        .enumerate()
+        .for_each(|(i, x)| positions[*x].0.push(i));
+    right
+        .iter()
+        .enumerate()
+        .for_each(|(i, x)| positions[*x].1.push(i));
+    positions.retain(|(left, right)| !left.is_empty() || !right.is_empty());
+
+    // best[k][s] is the fewest items moved when the first k types put s
+    // items in the first compartment
+    let mut best = vec![vec![None; half + 1]; positions.len() + 1];
+    best[0][0] = Some(0);
+
+    for (k, (left, right)) in positions.iter().enumerate() {
+        let count = left.len() + right.len();
+        for s in 0..=half {
+            let Some(moved) = best[k][s] else { continue };
+            let options = [(s, moved + left.len()), (s + count, moved + right.len())];
+            for (s, moved) in options {
+                if s <= half && best[k + 1][s].is_none_or(|x| moved < x) {
+                    best[k + 1][s] = Some(moved);
+                }
+            }
+        }
+    }
+
+    if best[positions.len()][half].is_none() {
+        return Ok(SackPlan {
+            line: sack.line,
+            swaps: None,
+        });
+    }
+
+    // Walk back through the table to find which side each type went to
+    let (mut outgoing, mut incoming) = (Vec::new(), Vec::new());
+    let mut s = half;
+    for (k, (left, right)) in positions.iter().enumerate().rev() {
+        let count = left.len() + right.len();
+        let kept_left = s >= count && best[k][s - count].map(|x| x + right.len()) == best[k + 1][s];
+
+        if kept_left {
+            incoming.extend(right);
+            s -= count;
+        } else {
+            outgoing.extend(left);
+        }
+    }
+
+    outgoing.sort_unstable();
+    incoming.sort_unstable();
+    let swaps = outgoing
+        .into_iter()
+        .zip(incoming)
+        .map(|(left, right)| Swap { left, right })
+        .collect();
+
+    Ok(SackPlan {
+        line: sack.line,
+        swaps: Some(swaps),
+    })
+}
+
+/// Applies `swaps` to a rucksack, returning its repacked items
+pub fn apply(sack: &str, swaps: &[Swap]) -> String {
+    let mut items: Vec<char> = sack.chars().collect();
+    let half = items.len() / 2;
+    for swap in swaps {
+        items.swap(swap.left, half + swap.right);
+    }
+    items.into_iter().collect()
+}
+
+/// Checks that `swaps` leave no item type in both compartments of `sack`
+pub fn verify(alphabet: &Alphabet, sack: &Rucksack, swaps: &[Swap]) -> Result<bool, RucksackError> {
+    let repacked = apply(sack.items, swaps);
+    let items = compartment_items(
+        alphabet,
+        &Rucksack {
+            items: &repacked,
+            ..*sack
+        },
+    )?;
+
+    let (left, right) = items.split_at(items.len() / 2);
+    Ok(shared_item(alphabet, left, right).is_none())
+}
+
+impl Display for SackPlan {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.swaps {
+            None => write!(f, "line {}: infeasible", self.line),
+            Some(swaps) => {
+                write!(f, "line {}: {} swaps", self.line, swaps.len())?;
+                for swap in swaps {
+                    write!(f, " (left {} <-> right {})", swap.left, swap.right)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sack(items: &str) -> Rucksack<'_> {
+        Rucksack {
+            line: 1,
+            key: None,
+            items,
+        }
+    }
+
+    fn swaps(items: &str) -> Option<usize> {
+        let alphabet = Alphabet::standard();
+        let sack = sack(items);
+        let plan = plan(&alphabet, &sack).unwrap();
+
+        let swaps = plan.swaps?;
+        assert!(verify(&alphabet, &sack, &swaps).unwrap());
+        Some(swaps.len())
+    }
+
+    #[test]
+    fn finds_the_fewest_swaps() {
+        assert_eq!(swaps("abcd"), Some(0));
+        assert_eq!(swaps("abba"), Some(1));
+        assert_eq!(swaps("aabbbbaa"), Some(2));
+        // Moving the lone 'c' across is cheaper than moving the 'b's
+        assert_eq!(swaps("aaacbbba"), Some(1));
+        assert_eq!(swaps(""), Some(0));
+    }
+
+    #[test]
+    fn reports_infeasible_rucksacks() {
+        assert_eq!(swaps("aaab"), None);
+        assert_eq!(swaps("aaaaaabb"), None);
+    }
+
+    #[test]
+    fn repacks_the_puzzle_input() {
+        let alphabet = Alphabet::standard();
+        let sacks = crate::rucksacks(include_str!("input.txt"), false).unwrap();
+
+        for sack in &sacks {
+            let plan = plan(&alphabet, sack).unwrap();
+            if let Some(swaps) = plan.swaps {
+                assert!(!swaps.is_empty());
+                assert!(verify(&alphabet, sack, &swaps).unwrap());
+            }
+        }
+    }
+}
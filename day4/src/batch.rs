@@ -0,0 +1,113 @@
+use std::simd::{cmp::SimdPartialOrd, u8x32, Mask};
+
+use crate::{pairs, range::NaiveRange, LineError};
+
+const LANES: usize = 32;
+
+/// Every pair in a file, stored as one column per boundary so that many
+/// pairs can be compared at once
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Batch {
+    left_begin: Vec<u8>,
+    left_end: Vec<u8>,
+    right_begin: Vec<u8>,
+    right_end: Vec<u8>,
+}
+
+impl Batch {
+    /// Reads every pair, which must use sections that fit in a `u8`
+    pub fn parse(input: &str) -> Result<Self, LineError> {
+        let mut batch = Batch::default();
+        for pair in pairs::<u8, NaiveRange<u8>>(input) {
+            let (left, right) = pair?;
+            batch.left_begin.push(left.0);
+            batch.left_end.push(left.1);
+            batch.right_begin.push(right.0);
+            batch.right_end.push(right.1);
+        }
+        Ok(batch)
+    }
+
+    pub fn len(&self) -> usize {
+        self.left_begin.len()
+    }
+
+    /// Counts the pairs for which `simd` holds, `LANES` pairs at a time,
+    /// falling back to `scalar` for the last few
+    fn count(
+        &self,
+        simd: impl Fn(u8x32, u8x32, u8x32, u8x32) -> Mask<i8, LANES>,
+        scalar: impl Fn(u8, u8, u8, u8) -> bool,
+    ) -> usize {
+        let columns = [
+            &self.left_begin,
+            &self.left_end,
+            &self.right_begin,
+            &self.right_end,
+        ];
+        let full = self.len() / LANES * LANES;
+
+        let bulk: usize = (0..full)
+            .step_by(LANES)
+            .map(|i| {
+                let [a, b, c, d] = columns.map(|x| u8x32::from_slice(&x[i..i + LANES]));
+                simd(a, b, c, d).to_bitmask().count_ones() as usize
+            })
+            .sum();
+
+        let rest = (full..self.len())
+            .filter(|&i| {
+                let [a, b, c, d] = columns.map(|x| x[i]);
+                scalar(a, b, c, d)
+            })
+            .count();
+
+        bulk + rest
+    }
+
+    /// How many pairs have one range containing the other
+    pub fn full_overlaps(&self) -> usize {
+        self.count(
+            |a, b, c, d| (a.simd_ge(c) & b.simd_le(d)) | (a.simd_le(c) & b.simd_ge(d)),
+            |a, b, c, d| (a >= c && b <= d) || (a <= c && b >= d),
+        )
+    }
+
+    /// How many pairs share any section
+    pub fn any_overlaps(&self) -> usize {
+        self.count(
+            |a, b, c, d| a.simd_le(d) & b.simd_ge(c),
+            |a, b, c, d| a <= d && b >= c,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::range::{Overlap, Range};
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn agrees_with_naive(pairs in prop::collection::vec((0..=255u8, 0..=255u8, 0..=255u8, 0..=255u8), 0..100)) {
+            let input: String = pairs
+                .iter()
+                .map(|(a, b, c, d)| format!("{}-{},{}-{}\n", a.min(b), a.max(b), c.min(d), c.max(d)))
+                .collect();
+            let batch = Batch::parse(&input).unwrap();
+
+            let naive: Vec<_> = input
+                .lines()
+                .map(|x| {
+                    let (left, right) = x.split_once(',').unwrap();
+                    let range = |x| NaiveRange::<u8>::from_string(x).unwrap();
+                    (range(left), range(right))
+                })
+                .collect();
+
+            prop_assert_eq!(batch.full_overlaps(), naive.iter().filter(|(a, b)| a.full_overlap(b)).count());
+            prop_assert_eq!(batch.any_overlaps(), naive.iter().filter(|(a, b)| a.any_overlap(b)).count());
+        }
+    }
+}
@@ -0,0 +1,236 @@
+use std::fmt::Display;
+
+use crate::range::{BitMask, NaiveRange, Section};
+
+/// A set of sections, stored as the sorted list of inclusive intervals it
+/// covers. Neighbouring intervals are always merged, so two sets holding
+/// the same sections compare equal.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IntervalSet {
+    intervals: Vec<(u64, u64)>,
+}
+
+impl IntervalSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Builds a set from intervals in any order, which may overlap
+    pub fn from_intervals(intervals: impl IntoIterator<Item = (u64, u64)>) -> Self {
+        let mut intervals: Vec<_> = intervals.into_iter().filter(|(a, b)| a <= b).collect();
+        intervals.sort_unstable();
+
+        let mut merged: Vec<(u64, u64)> = Vec::with_capacity(intervals.len());
+        for (begin, end) in intervals {
+            match merged.last_mut() {
+                Some(last) if begin <= last.1.saturating_add(1) => last.1 = last.1.max(end),
+                _ => merged.push((begin, end)),
+            }
+        }
+
+        IntervalSet { intervals: merged }
+    }
+
+    /// How many sections the set covers. This is a `u128`, since a set of
+    /// every `u64` section covers one more than `u64::MAX`.
+    pub fn len(&self) -> u128 {
+        self.intervals
+            .iter()
+            .map(|(a, b)| (b - a) as u128 + 1)
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.intervals.is_empty()
+    }
+
+    /// The first and last sections covered
+    pub fn span(&self) -> Option<(u64, u64)> {
+        Some((self.intervals.first()?.0, self.intervals.last()?.1))
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        Self::from_intervals(self.intervals.iter().chain(&other.intervals).copied())
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        let (mut a, mut b) = (
+            self.intervals.iter().peekable(),
+            other.intervals.iter().peekable(),
+        );
+        let mut intervals = Vec::new();
+
+        while let (Some(&&x), Some(&&y)) = (a.peek(), b.peek()) {
+            let (begin, end) = (x.0.max(y.0), x.1.min(y.1));
+            if begin <= end {
+                intervals.push((begin, end));
+            }
+
+            // Whichever interval ends first can't meet anything else
+            if x.1 < y.1 {
+                a.next();
+            } else {
+                b.next();
+            }
+        }
+
+        IntervalSet { intervals }
+    }
+
+    /// The sections in `universe` that aren't in the set
+    pub fn complement(&self, universe: (u64, u64)) -> Self {
+        let (first, last) = universe;
+        let mut intervals = Vec::new();
+        let mut next = Some(first);
+
+        for &(begin, end) in &self.intervals {
+            let Some(from) = next else { break };
+            if begin > from {
+                intervals.push((from, (begin - 1).min(last)));
+            }
+            next = end.checked_add(1).map(|x| x.max(from));
+        }
+
+        if let Some(from) = next {
+            intervals.push((from, last));
+        }
+
+        Self::from_intervals(intervals)
+    }
+
+    pub fn difference(&self, other: &Self) -> Self {
+        match self.span() {
+            Some(span) => self.intersection(&other.complement(span)),
+            None => Self::new(),
+        }
+    }
+
+    /// The holes between the first and last sections covered
+    pub fn gaps(&self) -> Self {
+        match self.span() {
+            Some(span) => self.complement(span),
+            None => Self::new(),
+        }
+    }
+}
+
+impl<S: Section> From<NaiveRange<S>> for IntervalSet {
+    fn from(range: NaiveRange<S>) -> Self {
+        Self::from_intervals([(range.0.into(), range.1.into())])
+    }
+}
+
+impl From<BitMask> for IntervalSet {
+    fn from(mask: BitMask) -> Self {
+        Self::from_intervals(mask.sections().map(|x| (x as u64, x as u64)))
+    }
+}
+
+impl Display for IntervalSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_empty() {
+            return write!(f, "none");
+        }
+
+        for (i, (begin, end)) in self.intervals.iter().enumerate() {
+            if i > 0 {
+                write!(f, ",")?;
+            }
+            match begin == end {
+                true => write!(f, "{}", begin)?,
+                false => write!(f, "{}-{}", begin, end)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::range::Range;
+    use proptest::prelude::*;
+
+    fn set(intervals: &[(u64, u64)]) -> IntervalSet {
+        IntervalSet::from_intervals(intervals.iter().copied())
+    }
+
+    #[test]
+    fn merges_touching_intervals() {
+        assert_eq!(
+            set(&[(5, 9), (1, 3), (4, 4), (20, 30), (25, 26)]),
+            set(&[(1, 9), (20, 30)])
+        );
+        assert_eq!(set(&[(1, 9), (20, 30)]).len(), 20);
+        assert_eq!(set(&[(0, u64::MAX)]).len(), u64::MAX as u128 + 1);
+        assert_eq!(
+            set(&[(1, 3), (7, 7), (10, 12)]).gaps(),
+            set(&[(4, 6), (8, 9)])
+        );
+        assert_eq!(set(&[(0, u64::MAX)]).complement((0, u64::MAX)), set(&[]));
+        assert_eq!(set(&[(3, 4)]).complement((0, 9)).to_string(), "0-2,5-9");
+    }
+
+    /// Up to three ranges of sections, which is enough to get gaps,
+    /// overlaps and ranges that touch
+    fn ranges() -> impl Strategy<Value = Vec<(u8, u8)>> {
+        prop::collection::vec((0..=127u8, 0..=127u8), 0..=3)
+            .prop_map(|x| x.into_iter().map(|(a, b)| (a.min(b), a.max(b))).collect())
+    }
+
+    /// Builds a set both ways: from naive ranges and from bitmasks
+    fn both_ways(ranges: &[(u8, u8)]) -> (IntervalSet, IntervalSet) {
+        let naive = ranges.iter().fold(IntervalSet::new(), |set, &(a, b)| {
+            set.union(&NaiveRange::from_range(a, b).unwrap().into())
+        });
+        let mask = ranges.iter().fold(IntervalSet::new(), |set, &(a, b)| {
+            set.union(&<BitMask as Range<u8>>::from_range(a, b).unwrap().into())
+        });
+        (naive, mask)
+    }
+
+    /// Which of the 128 sections are in the set
+    fn members(set: &IntervalSet) -> Vec<bool> {
+        (0..128)
+            .map(|x| set.intervals.iter().any(|(a, b)| (*a..=*b).contains(&x)))
+            .collect()
+    }
+
+    proptest! {
+        #[test]
+        fn representations_agree(a in ranges(), b in ranges()) {
+            let (a_naive, a_mask) = both_ways(&a);
+            let (b_naive, b_mask) = both_ways(&b);
+            prop_assert_eq!(&a_naive, &a_mask);
+            prop_assert_eq!(&b_naive, &b_mask);
+
+            prop_assert_eq!(a_naive.union(&b_naive), a_mask.union(&b_mask));
+            prop_assert_eq!(a_naive.intersection(&b_naive), a_mask.intersection(&b_mask));
+            prop_assert_eq!(a_naive.difference(&b_naive), a_mask.difference(&b_mask));
+            prop_assert_eq!(a_naive.complement((0, 127)), a_mask.complement((0, 127)));
+            prop_assert_eq!(a_naive.gaps(), a_mask.gaps());
+        }
+
+        #[test]
+        fn operations_match_membership(a in ranges(), b in ranges()) {
+            let (a, _) = both_ways(&a);
+            let (b, _) = both_ways(&b);
+            let (x, y) = (members(&a), members(&b));
+            let expected = |f: fn(bool, bool) -> bool| -> Vec<bool> {
+                x.iter().zip(&y).map(|(x, y)| f(*x, *y)).collect()
+            };
+
+            prop_assert_eq!(members(&a.union(&b)), expected(|x, y| x || y));
+            prop_assert_eq!(members(&a.intersection(&b)), expected(|x, y| x && y));
+            prop_assert_eq!(members(&a.difference(&b)), expected(|x, y| x && !y));
+            prop_assert_eq!(members(&a.complement((0, 127))), expected(|x, _| !x));
+            prop_assert_eq!(a.len(), x.iter().filter(|x| **x).count() as u128);
+
+            let gaps = members(&a.gaps());
+            let span = a.span().map_or(0..0, |(a, b)| a as usize..b as usize + 1);
+            for (i, gap) in gaps.iter().enumerate() {
+                prop_assert_eq!(*gap, span.contains(&i) && !x[i]);
+            }
+        }
+    }
+}
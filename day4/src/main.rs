@@ -1,84 +1,379 @@
-#![feature(test)]
+#![feature(portable_simd)]
+#![cfg_attr(test, feature(test))]
 
-trait Range {
-    fn from_string(range: &str) -> Option<Self>
-    where
-        Self: Sized,
-    {
-        let (begin, end) = range.split_once('-')?;
-        let begin: u8 = begin.parse().ok()?;
-        let end: u8 = end.parse().ok()?;
+use anyhow::{anyhow, bail, Context, Result};
+use thiserror::Error;
 
-        Some(Self::from_range(begin, end))
+use intervals::IntervalSet;
+use range::{BitMask, NaiveRange, Overlap, Range, RangeError, Section, U128Mask, WideMask};
+
+mod batch;
+mod intervals;
+mod range;
+mod reassign;
+mod sweep;
+mod tree;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum LineError {
+    #[error(r#"Line {0}: expected "<range>,<range>""#)]
+    NotAPair(usize),
+    #[error("Line {line}: {error}")]
+    BadRange { line: usize, error: RangeError },
+}
+
+fn pairs<S: Section, T: Range<S>>(
+    input: &str,
+) -> impl Iterator<Item = Result<(T, T), LineError>> + '_ {
+    input.lines().enumerate().map(|(i, x)| {
+        let line = i + 1;
+        let (left, right) = x.split_once(',').ok_or(LineError::NotAPair(line))?;
+        let range = |x| T::from_string(x).map_err(|error| LineError::BadRange { line, error });
+
+        Ok((range(left)?, range(right)?))
+    })
+}
+
+fn part1<S: Section, T: Range<S>>(input: &str) -> Result<usize, LineError> {
+    pairs::<S, T>(input).try_fold(0, |count, pair| {
+        let (left, right) = pair?;
+        Ok(count + left.full_overlap(&right) as usize)
+    })
+}
+
+fn part2<S: Section, T: Range<S>>(input: &str) -> Result<usize, LineError> {
+    pairs::<S, T>(input).try_fold(0, |count, pair| {
+        let (left, right) = pair?;
+        Ok(count + left.any_overlap(&right) as usize)
+    })
+}
+
+fn solve<S: Section, T: Range<S>>(input: &str) -> Result<()> {
+    let p1 = part1::<S, T>(input).context("Failed to complete part 1")?;
+    let p2 = part2::<S, T>(input).context("Failed to complete part 2")?;
+
+    println!("P1: {}, P2: {}", p1, p2);
+    Ok(())
+}
+
+/// Solves both parts with queries against an interval tree of every
+/// assignment in the file
+fn solve_tree(input: &str) -> Result<()> {
+    let pairs = pairs::<u64, NaiveRange<u64>>(input).collect::<Result<Vec<_>, _>>()?;
+    let assignments = sweep::assignments(&pairs);
+
+    println!(
+        "P1: {}, P2: {}",
+        tree::part1(&assignments),
+        tree::part2(&assignments)
+    );
+    Ok(())
+}
+
+/// Prints which assignments match a query against the interval tree
+fn query(input: &str, queries: &[(String, NaiveRange<u64>)]) -> Result<()> {
+    let pairs = pairs::<u64, NaiveRange<u64>>(input).collect::<Result<Vec<_>, _>>()?;
+    let assignments = sweep::assignments(&pairs);
+    let tree = tree::IntervalTree::new(&assignments);
+
+    for (kind, range) in queries {
+        let found = match kind.as_str() {
+            "--stab" => tree.stabbing(range.0),
+            "--overlapping" => tree.overlapping(range.0, range.1),
+            _ => tree.containing(range.0, range.1),
+        };
+
+        println!(
+            "{} {}-{}: {} assignments",
+            kind,
+            range.0,
+            range.1,
+            found.len()
+        );
+        for x in found.iter().map(|x| assignments[*x]) {
+            println!("line {} elf {} ({}-{})", x.line, x.elf + 1, x.begin, x.end);
+        }
     }
+    Ok(())
+}
+
+/// Solves both parts by comparing many pairs at once
+fn solve_batch(input: &str) -> Result<()> {
+    let batch = batch::Batch::parse(input).context("Failed to read the assignments")?;
+    println!(
+        "P1: {}, P2: {}",
+        batch.full_overlaps(),
+        batch.any_overlaps()
+    );
+    Ok(())
+}
 
-    fn from_range(begin: u8, end: u8) -> Self;
-    fn full_overlap(self, other: Self) -> bool;
-    fn any_overlap(self, other: Self) -> bool;
+fn solve_with<S: Section>(range: &str, input: &str) -> Result<()> {
+    match range {
+        "simd" => solve_batch(input),
+        "tree" => solve_tree(input),
+        "bitmask" => solve::<S, BitMask>(input),
+        "u128" => solve::<S, U128Mask>(input),
+        "wide" => solve::<S, WideMask>(input),
+        "naive" => solve::<S, NaiveRange<S>>(input),
+        _ => bail!("{}", USAGE),
+    }
 }
 
-#[derive(Copy, Clone, Debug)]
-struct BitMask(u128);
+/// Prints how the assignments cover the sections, across the whole file
+fn coverage<S: Section, T: Range<S> + Into<IntervalSet>>(
+    input: &str,
+    universe: Option<(u64, u64)>,
+) -> Result<()> {
+    let mut covered = IntervalSet::new();
+    let (mut shared, mut exclusive) = (0, 0);
+
+    for pair in pairs::<S, T>(input) {
+        let (left, right) = pair?;
+        let (left, right): (IntervalSet, IntervalSet) = (left.into(), right.into());
 
-impl Range for BitMask {
-    fn from_range(begin: u8, end: u8) -> Self {
-        let (shl, shr) = (begin, 127 - end);
-        BitMask((u128::MAX.wrapping_shl(shl as u32)) & (u128::MAX.wrapping_shr(shr as u32)))
+        shared += left.intersection(&right).len();
+        exclusive += left.difference(&right).len() + right.difference(&left).len();
+        covered = covered.union(&left).union(&right);
     }
 
-    fn full_overlap(self: BitMask, b: BitMask) -> bool {
-        let overlap = self.0 & b.0;
-        let count = overlap.count_ones();
-        count == self.0.count_ones() || count == b.0.count_ones()
+    println!("Covered: {} sections ({})", covered.len(), covered);
+    println!("Gaps: {}", covered.gaps());
+    println!("Assigned to both elves on a line: {} sections", shared);
+    println!("Assigned to just one elf on a line: {} sections", exclusive);
+
+    if let Some(universe) = universe.or(covered.span()) {
+        let uncovered = covered.complement(universe);
+        println!(
+            "Uncovered in {}-{}: {} sections ({})",
+            universe.0,
+            universe.1,
+            uncovered.len(),
+            uncovered
+        );
     }
+    Ok(())
+}
 
-    fn any_overlap(self, other: Self) -> bool {
-        (self.0 & other.0) > 0
+fn coverage_with<S: Section>(range: &str, input: &str, universe: Option<(u64, u64)>) -> Result<()> {
+    match range {
+        "bitmask" => coverage::<S, BitMask>(input, universe),
+        "naive" => coverage::<S, NaiveRange<S>>(input, universe),
+        _ => bail!("Only the bitmask and naive ranges can be turned into interval sets"),
     }
 }
 
-#[derive(Copy, Clone, Debug)]
-struct NaiveRange(u8, u8);
+/// Prints how deeply the assignments in the whole file overlap
+fn depth(input: &str, k: usize, universe: Option<(u64, u64)>, list_pairs: bool) -> Result<()> {
+    let pairs = pairs::<u64, NaiveRange<u64>>(input).collect::<Result<Vec<_>, _>>()?;
+    let assignments = sweep::assignments(&pairs);
+    let depths = sweep::depths(&assignments);
 
-impl Range for NaiveRange {
-    fn from_range(begin: u8, end: u8) -> Self {
-        NaiveRange(begin, end)
-    }
+    let (max, at) = sweep::max_depth(&depths);
+    println!("Max depth: {} elves, at {}", max, at);
 
-    fn full_overlap(self, other: Self) -> bool {
-        return (self.0 >= other.0 && self.1 <= other.1)
-            || (self.0 <= other.0 && self.1 >= other.1);
+    let deep = sweep::deeper_than(&depths, k);
+    println!(
+        "Covered by more than {} elves: {} sections ({})",
+        k,
+        deep.len(),
+        deep
+    );
+
+    let covered = sweep::deeper_than(&depths, 0);
+    if let Some(universe) = universe.or(covered.span()) {
+        let uncovered = covered.complement(universe);
+        println!(
+            "Uncovered in {}-{}: {} sections ({})",
+            universe.0,
+            universe.1,
+            uncovered.len(),
+            uncovered
+        );
     }
 
-    fn any_overlap(self, other: Self) -> bool {
-        return self.0 <= other.1 && self.1 >= other.0;
+    let overlapping = sweep::overlapping_pairs(&assignments);
+    println!("Overlapping pairs across lines: {}", overlapping.len());
+    if list_pairs {
+        for (a, b) in overlapping {
+            let (a, b) = (assignments[a], assignments[b]);
+            println!(
+                "line {} elf {} ({}-{}) and line {} elf {} ({}-{})",
+                a.line,
+                a.elf + 1,
+                a.begin,
+                a.end,
+                b.line,
+                b.elf + 1,
+                b.begin,
+                b.end
+            );
+        }
     }
+    Ok(())
 }
 
-fn part1<T: Range + Copy + Clone>(input: &str) -> usize {
-    input
-        .lines()
-        .flat_map(|x| x.split_once(','))
-        .flat_map(|(left, right)| T::from_string(left).zip(T::from_string(right)))
-        .filter(|(left, right)| left.full_overlap(*right))
-        .count()
+/// Prints the cheapest reassignment of every line that doesn't meet `goal`
+fn reassign(input: &str, goal: reassign::Goal) -> Result<()> {
+    let pairs = pairs::<u64, NaiveRange<u64>>(input).collect::<Result<Vec<_>, _>>()?;
+    let reassignments = reassign::reassign(&pairs, goal);
+
+    for x in &reassignments {
+        if !reassign::verify(x, goal) {
+            bail!("Line {}: the reassignment doesn't meet the goal", x.line);
+        }
+
+        let (before, after) = (x.before, x.after);
+        println!(
+            "line {}: {}-{},{}-{} -> {}-{},{}-{} (cost {})",
+            x.line,
+            before.0 .0,
+            before.0 .1,
+            before.1 .0,
+            before.1 .1,
+            after.0 .0,
+            after.0 .1,
+            after.1 .0,
+            after.1 .1,
+            x.cost
+        );
+    }
+
+    let unchanged = pairs.iter().filter(|x| x.0.any_overlap(&x.1)).count() - reassignments.len();
+    println!(
+        "Reassigned {} lines, total cost {}",
+        reassignments.len(),
+        reassignments.iter().map(|x| x.cost).sum::<u128>()
+    );
+    if unchanged > 0 {
+        println!("{} overlapping lines couldn't be improved", unchanged);
+    }
+    Ok(())
 }
 
-fn part2<T: Range + Copy>(input: &str) -> usize {
-    input
-        .lines()
-        .flat_map(|x| x.split_once(','))
-        .flat_map(|(left, right)| T::from_string(left).zip(T::from_string(right)))
-        .filter(|(left, right)| left.any_overlap(*right))
-        .count()
+const USAGE: &str = "\
+usage: day4 [options] [intervals|depth|query|reassign]
+
+commands:
+    intervals                           report how the assignments cover the sections
+    depth                               report how many elves cover each section, across
+                                        the whole file
+    query                               list the assignments matching each --stab,
+                                        --overlapping and --containing option
+    reassign                            move the fewest boundaries so no two elves on a
+                                        line share a section
+
+options:
+    --input <path>                      read the assignments from a file instead of the puzzle input
+    --range <bitmask|u128|wide|naive|tree|simd>
+                                        how to represent each range (default bitmask, which
+                                        like u128 only holds sections up to 127); wide
+                                        holds any sections, in ranges of up to 1048576;
+                                        tree answers both parts with interval tree
+                                        queries, and simd compares many pairs at once,
+                                        with sections up to 255
+    --sections <u8|u16|u32>             the integer type of the section numbers (default u8)
+    --universe <begin-end>              the sections the intervals report looks for gaps in
+                                        (default from the first to the last one covered)
+    --more-than <k>                     the depth report lists sections covered by more
+                                        than k elves (default 1)
+    --list-pairs                        the depth report lists every pair of assignments
+                                        on different lines that overlap
+    --stab <section>                    query the assignments containing a section
+    --overlapping <begin-end>           query the assignments sharing any of a range
+    --containing <begin-end>            query the assignments containing all of a range
+    --keep-coverage                     reassign so each line still covers the same
+                                        sections, sharing as few as possible";
+
+fn run(input: &str) -> Result<()> {
+    let mut assignments = None;
+    let mut range = "bitmask".to_owned();
+    let mut sections = "u8".to_owned();
+    let mut universe = None;
+    let mut command = None;
+    let mut more_than = 1;
+    let mut list_pairs = false;
+    let mut queries = Vec::new();
+    let mut goal = reassign::Goal::Disjoint;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| anyhow!("Expected a value after '{}'", arg))
+        };
+
+        match arg.as_str() {
+            "--input" => {
+                let path = value()?;
+                let file = std::fs::read_to_string(&path)
+                    .with_context(|| format!("Couldn't read '{}'", path))?;
+                assignments = Some(file);
+            }
+            "--range" => range = value()?,
+            "--sections" => sections = value()?,
+            "--universe" => {
+                let range: NaiveRange<u64> =
+                    Range::from_string(&value()?).context("Failed to parse the universe")?;
+                universe = Some((range.0, range.1));
+            }
+            "--more-than" => {
+                let k = value()?;
+                more_than = k
+                    .parse()
+                    .with_context(|| format!("Expected a number of elves, but found '{}'", k))?;
+            }
+            "--list-pairs" => list_pairs = true,
+            "--keep-coverage" => goal = reassign::Goal::KeepCoverage,
+            "--stab" | "--overlapping" | "--containing" => {
+                let value = value()?;
+                let range = match arg.as_str() {
+                    "--stab" => Range::from_string(&format!("{}-{}", value, value)),
+                    _ => Range::from_string(&value),
+                };
+                let range: NaiveRange<u64> =
+                    range.with_context(|| format!("Failed to parse the query '{}'", value))?;
+                queries.push((arg, range));
+            }
+            "intervals" | "depth" | "query" | "reassign" => command = Some(arg),
+            _ => bail!("{}", USAGE),
+        }
+    }
+
+    let input = assignments.as_deref().unwrap_or(input);
+    if command.as_deref() == Some("reassign") {
+        return reassign(input, goal);
+    }
+
+    if command.as_deref() == Some("query") {
+        return query(input, &queries);
+    }
+
+    if command.as_deref() == Some("depth") {
+        return depth(input, more_than, universe, list_pairs);
+    }
+
+    if command.as_deref() == Some("intervals") {
+        return match sections.as_str() {
+            "u8" => coverage_with::<u8>(&range, input, universe),
+            "u16" => coverage_with::<u16>(&range, input, universe),
+            "u32" => coverage_with::<u32>(&range, input, universe),
+            _ => bail!("{}", USAGE),
+        };
+    }
+
+    match sections.as_str() {
+        "u8" => solve_with::<u8>(&range, input),
+        "u16" => solve_with::<u16>(&range, input),
+        "u32" => solve_with::<u32>(&range, input),
+        _ => bail!("{}", USAGE),
+    }
 }
 
-fn main() {
+fn main() -> Result<()> {
     let input = include_str!("input.txt");
-    let p1 = part1::<BitMask>(input);
-    let p2 = part2::<BitMask>(input);
 
-    println!("P1: {}, P2: {}", p1, p2);
+    run(input)
 }
 
 #[cfg(test)]
@@ -88,35 +383,164 @@ mod tests {
     use super::*;
     use test::Bencher;
 
-    const INPUT: &'static str = include_str!("input.txt");
+    const INPUT: &str = include_str!("input.txt");
 
     #[test]
     fn bitmask_eq_naive_p1() {
-        assert_eq!(part1::<BitMask>(INPUT), part1::<NaiveRange>(INPUT))
+        assert_eq!(
+            part1::<u8, BitMask>(INPUT),
+            part1::<u8, NaiveRange<u8>>(INPUT)
+        )
     }
 
     #[test]
     fn bitmask_eq_naive_p2() {
-        assert_eq!(part2::<BitMask>(INPUT), part2::<NaiveRange>(INPUT))
+        assert_eq!(
+            part2::<u8, BitMask>(INPUT),
+            part2::<u8, NaiveRange<u8>>(INPUT)
+        )
+    }
+
+    #[test]
+    fn wide_eq_naive() {
+        assert_eq!(
+            part1::<u32, WideMask>(INPUT),
+            part1::<u32, NaiveRange<u32>>(INPUT)
+        );
+        assert_eq!(
+            part2::<u32, WideMask>(INPUT),
+            part2::<u32, NaiveRange<u32>>(INPUT)
+        );
+    }
+
+    #[test]
+    fn reports_the_bad_line() {
+        assert_eq!(
+            part1::<u8, BitMask>("1-2,3-4\n2-200,4-5"),
+            Err(LineError::BadRange {
+                line: 2,
+                error: RangeError::TooWide { end: 200, max: 127 }
+            })
+        );
+        assert_eq!(
+            part2::<u8, NaiveRange<u8>>("1-2,3-4\n1-2"),
+            Err(LineError::NotAPair(2))
+        );
+    }
+
+    #[test]
+    fn tree_eq_naive() {
+        let pairs: Vec<_> = pairs::<u64, NaiveRange<u64>>(INPUT)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        let assignments = sweep::assignments(&pairs);
+
+        assert_eq!(
+            Ok(tree::part1(&assignments)),
+            part1::<u8, NaiveRange<u8>>(INPUT)
+        );
+        assert_eq!(
+            Ok(tree::part2(&assignments)),
+            part2::<u8, NaiveRange<u8>>(INPUT)
+        );
+    }
+
+    #[test]
+    fn simd_eq_naive() {
+        let batch = batch::Batch::parse(INPUT).unwrap();
+        assert_eq!(
+            Ok(batch.full_overlaps()),
+            part1::<u8, NaiveRange<u8>>(INPUT)
+        );
+        assert_eq!(Ok(batch.any_overlaps()), part2::<u8, NaiveRange<u8>>(INPUT));
+    }
+
+    #[test]
+    fn bitmask_eq_u128() {
+        assert_eq!(part1::<u8, BitMask>(INPUT), part1::<u8, U128Mask>(INPUT));
+        assert_eq!(part2::<u8, BitMask>(INPUT), part2::<u8, U128Mask>(INPUT));
     }
 
     #[bench]
     fn part1_bitmask(bench: &mut Bencher) {
-        bench.iter(|| part1::<BitMask>(INPUT));
+        bench.iter(|| part1::<u8, BitMask>(INPUT));
+    }
+
+    #[bench]
+    fn part1_u128(bench: &mut Bencher) {
+        bench.iter(|| part1::<u8, U128Mask>(INPUT));
+    }
+
+    #[bench]
+    fn part1_wide(bench: &mut Bencher) {
+        bench.iter(|| part1::<u8, WideMask>(INPUT));
+    }
+
+    #[bench]
+    fn part1_tree(bench: &mut Bencher) {
+        let pairs: Vec<_> = pairs::<u64, NaiveRange<u64>>(INPUT)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        let assignments = sweep::assignments(&pairs);
+        bench.iter(|| tree::part1(&assignments));
+    }
+
+    #[bench]
+    fn part1_simd(bench: &mut Bencher) {
+        bench.iter(|| batch::Batch::parse(INPUT).unwrap().full_overlaps());
+    }
+
+    // Parsing dominates the part benchmarks, so time the comparisons alone
+    #[bench]
+    fn part1_simd_compare_only(bench: &mut Bencher) {
+        let batch = batch::Batch::parse(INPUT).unwrap();
+        bench.iter(|| batch.full_overlaps());
+    }
+
+    #[bench]
+    fn part1_naive_compare_only(bench: &mut Bencher) {
+        let pairs: Vec<_> = pairs::<u8, NaiveRange<u8>>(INPUT)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        bench.iter(|| pairs.iter().filter(|(a, b)| a.full_overlap(b)).count());
     }
 
     #[bench]
     fn part1_naive(bench: &mut Bencher) {
-        bench.iter(|| part1::<NaiveRange>(INPUT));
+        bench.iter(|| part1::<u8, NaiveRange<u8>>(INPUT));
     }
 
     #[bench]
     fn part2_bitmask(bench: &mut Bencher) {
-        bench.iter(|| part2::<BitMask>(INPUT));
+        bench.iter(|| part2::<u8, BitMask>(INPUT));
+    }
+
+    #[bench]
+    fn part2_u128(bench: &mut Bencher) {
+        bench.iter(|| part2::<u8, U128Mask>(INPUT));
+    }
+
+    #[bench]
+    fn part2_wide(bench: &mut Bencher) {
+        bench.iter(|| part2::<u8, WideMask>(INPUT));
+    }
+
+    #[bench]
+    fn part2_tree(bench: &mut Bencher) {
+        let pairs: Vec<_> = pairs::<u64, NaiveRange<u64>>(INPUT)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        let assignments = sweep::assignments(&pairs);
+        bench.iter(|| tree::part2(&assignments));
+    }
+
+    #[bench]
+    fn part2_simd(bench: &mut Bencher) {
+        bench.iter(|| batch::Batch::parse(INPUT).unwrap().any_overlaps());
     }
 
     #[bench]
     fn part2_naive(bench: &mut Bencher) {
-        bench.iter(|| part2::<NaiveRange>(INPUT));
+        bench.iter(|| part2::<u8, NaiveRange<u8>>(INPUT));
     }
 }
@@ -0,0 +1,411 @@
+use std::{fmt::Debug, ops::Range as Indices, str::FromStr};
+
+use bitset::{ops, BitSet, Bits, FixedBitSet};
+use thiserror::Error;
+
+/// An integer type used to number sections
+pub trait Section: Copy + Ord + Debug + FromStr + Into<u64> {
+    fn index(self) -> usize {
+        self.into() as usize
+    }
+}
+
+impl Section for u8 {}
+impl Section for u16 {}
+impl Section for u32 {}
+impl Section for u64 {}
+
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+pub enum RangeError {
+    #[error(r#"expected "<begin>-<end>", but found "{0}""#)]
+    Malformed(String),
+    #[error(r#"expected a section number, but found "{0}""#)]
+    BadSection(String),
+    #[error("the range {begin}-{end} ends before it begins")]
+    Reversed { begin: u64, end: u64 },
+    #[error("section {end} is past the last section this representation can hold ({max})")]
+    TooWide { end: u64, max: u64 },
+    #[error(
+        "the range {begin}-{end} is longer than the {max} sections this representation can hold"
+    )]
+    TooLong { begin: u64, end: u64, max: u64 },
+}
+
+/// Compares two ranges of the same representation
+pub trait Overlap {
+    fn full_overlap(&self, other: &Self) -> bool;
+    fn any_overlap(&self, other: &Self) -> bool;
+}
+
+/// A representation of a range of sections numbered with `S`
+pub trait Range<S: Section>: Overlap {
+    fn from_string(range: &str) -> Result<Self, RangeError>
+    where
+        Self: Sized,
+    {
+        let (begin, end) = range
+            .split_once('-')
+            .ok_or_else(|| RangeError::Malformed(range.to_owned()))?;
+        let section = |x: &str| {
+            x.parse::<S>()
+                .map_err(|_| RangeError::BadSection(x.to_owned()))
+        };
+
+        Self::from_range(section(begin)?, section(end)?)
+    }
+
+    /// Checks that `begin..=end` is the right way round, and fits in the
+    /// representation
+    fn from_range(begin: S, end: S) -> Result<Self, RangeError>
+    where
+        Self: Sized,
+    {
+        if begin > end {
+            return Err(RangeError::Reversed {
+                begin: begin.into(),
+                end: end.into(),
+            });
+        }
+        if let Some(max) = Self::MAX_SECTION {
+            if end.into() > max {
+                return Err(RangeError::TooWide {
+                    end: end.into(),
+                    max,
+                });
+            }
+        }
+        if let Some(max) = Self::MAX_LENGTH {
+            if end.into() - begin.into() >= max {
+                return Err(RangeError::TooLong {
+                    begin: begin.into(),
+                    end: end.into(),
+                    max,
+                });
+            }
+        }
+
+        Ok(Self::from_range_unchecked(begin, end))
+    }
+
+    /// The last section the representation can hold, if it's limited
+    const MAX_SECTION: Option<u64> = None;
+
+    /// The most sections a single range can cover, if it's limited
+    const MAX_LENGTH: Option<u64> = None;
+
+    /// Builds `begin..=end`, which has already been checked
+    fn from_range_unchecked(begin: S, end: S) -> Self;
+}
+
+/// The sections covered by a range, one bit per section
+#[derive(Copy, Clone, Debug)]
+pub struct BitMask(FixedBitSet<2>);
+
+impl BitMask {
+    pub fn sections(&self) -> bitset::Iter<'_> {
+        self.0.iter()
+    }
+}
+
+impl<S: Section> Range<S> for BitMask {
+    const MAX_SECTION: Option<u64> = Some(127);
+
+    fn from_range_unchecked(begin: S, end: S) -> Self {
+        BitMask(FixedBitSet::from_range(begin.index()..end.index() + 1))
+    }
+}
+
+impl Overlap for BitMask {
+    fn full_overlap(&self, other: &Self) -> bool {
+        self.0.is_subset(&other.0) || other.0.is_subset(&self.0)
+    }
+
+    fn any_overlap(&self, other: &Self) -> bool {
+        self.0.intersects(&other.0)
+    }
+}
+
+/// The original hand-rolled mask, kept to benchmark [`BitMask`] against
+#[derive(Copy, Clone, Debug)]
+pub struct U128Mask(u128);
+
+impl<S: Section> Range<S> for U128Mask {
+    const MAX_SECTION: Option<u64> = Some(127);
+
+    fn from_range_unchecked(begin: S, end: S) -> Self {
+        let (shl, shr) = (begin.index(), 127 - end.index());
+        U128Mask((u128::MAX.wrapping_shl(shl as u32)) & (u128::MAX.wrapping_shr(shr as u32)))
+    }
+}
+
+impl Overlap for U128Mask {
+    fn full_overlap(&self, b: &U128Mask) -> bool {
+        let overlap = self.0 & b.0;
+        let count = overlap.count_ones();
+        count == self.0.count_ones() || count == b.0.count_ones()
+    }
+
+    fn any_overlap(&self, other: &Self) -> bool {
+        (self.0 & other.0) > 0
+    }
+}
+
+/// A bitmask for any section numbers. Only the words from the one holding
+/// `begin` to the one holding `end` are stored, so high section numbers
+/// don't need a huge mask. Long ranges still need one bit per section,
+/// so they're capped at [`WideMask::MAX_LENGTH`] sections (128 KiB).
+#[derive(Clone, Debug)]
+pub struct WideMask {
+    /// The index of the first stored word
+    offset: usize,
+    bits: BitSet,
+}
+
+impl WideMask {
+    pub const MAX_LENGTH: u64 = 1 << 20;
+
+    fn words(&self) -> Indices<usize> {
+        self.offset..self.offset + self.bits.words().len()
+    }
+
+    /// The words of both masks that cover the same sections
+    fn common<'a>(&'a self, other: &'a Self) -> (&'a [u64], &'a [u64]) {
+        let (ours, theirs) = (self.words(), other.words());
+        let start = ours.start.max(theirs.start);
+        let end = ours.end.min(theirs.end);
+        if start >= end {
+            return (&[], &[]);
+        }
+
+        (
+            &self.bits.words()[start - ours.start..end - ours.start],
+            &other.bits.words()[start - theirs.start..end - theirs.start],
+        )
+    }
+
+    fn is_subset(&self, other: &Self) -> bool {
+        let (ours, theirs) = (self.words(), other.words());
+        let outside = self
+            .bits
+            .words()
+            .iter()
+            .zip(ours)
+            .any(|(word, i)| *word != 0 && !theirs.contains(&i));
+
+        let (a, b) = self.common(other);
+        !outside && ops::is_subset(a, b)
+    }
+}
+
+impl<S: Section> Range<S> for WideMask {
+    const MAX_LENGTH: Option<u64> = Some(WideMask::MAX_LENGTH);
+
+    fn from_range_unchecked(begin: S, end: S) -> Self {
+        let (begin, end) = (begin.index(), end.index());
+        let offset = begin / 64;
+        let mut bits = BitSet::with_capacity((end / 64 - offset + 1) * 64);
+        bits.insert_range(begin - offset * 64..end - offset * 64 + 1);
+
+        WideMask { offset, bits }
+    }
+}
+
+impl Overlap for WideMask {
+    fn full_overlap(&self, other: &Self) -> bool {
+        self.is_subset(other) || other.is_subset(self)
+    }
+
+    fn any_overlap(&self, other: &Self) -> bool {
+        let (a, b) = self.common(other);
+        ops::intersects(a, b)
+    }
+}
+
+/// The reference implementation, which just compares the bounds
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct NaiveRange<S>(pub S, pub S);
+
+impl<S: Section> Range<S> for NaiveRange<S> {
+    fn from_range_unchecked(begin: S, end: S) -> Self {
+        NaiveRange(begin, end)
+    }
+}
+
+impl<S: Section> Overlap for NaiveRange<S> {
+    fn full_overlap(&self, other: &Self) -> bool {
+        (self.0 >= other.0 && self.1 <= other.1) || (self.0 <= other.0 && self.1 >= other.1)
+    }
+
+    fn any_overlap(&self, other: &Self) -> bool {
+        self.0 <= other.1 && self.1 >= other.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn rejects_bad_ranges() {
+        assert_eq!(
+            <NaiveRange<u8> as Range<u8>>::from_string("5-3").unwrap_err(),
+            RangeError::Reversed { begin: 5, end: 3 }
+        );
+        assert_eq!(
+            <BitMask as Range<u8>>::from_string("3-128").unwrap_err(),
+            RangeError::TooWide { end: 128, max: 127 }
+        );
+        assert_eq!(
+            <NaiveRange<u8> as Range<u8>>::from_string("3-256").unwrap_err(),
+            RangeError::BadSection("256".to_owned())
+        );
+        assert_eq!(
+            <NaiveRange<u8> as Range<u8>>::from_string("3").unwrap_err(),
+            RangeError::Malformed("3".to_owned())
+        );
+        assert_eq!(
+            <WideMask as Range<u32>>::from_string("0-4294967295").unwrap_err(),
+            RangeError::TooLong {
+                begin: 0,
+                end: u32::MAX as u64,
+                max: WideMask::MAX_LENGTH
+            }
+        );
+        assert!(<WideMask as Range<u32>>::from_range(5, WideMask::MAX_LENGTH as u32 + 4).is_ok());
+        assert!(<WideMask as Range<u32>>::from_range(5, WideMask::MAX_LENGTH as u32 + 5).is_err());
+        assert!(<WideMask as Range<u32>>::from_range(4_294_000_000, u32::MAX).is_ok());
+    }
+
+    #[test]
+    fn wide_masks_agree_with_naive() {
+        let ranges = [
+            (0, 0),
+            (0, 63),
+            (64, 64),
+            (60, 200),
+            (127, 128),
+            (199, 1000),
+            (1000, 1000),
+            (4_000_000_000, 4_000_000_100),
+            (3_999_999_990, 4_000_000_000),
+        ];
+
+        for a in ranges {
+            for b in ranges {
+                let naive = |(x, y)| NaiveRange::<u32>::from_range(x, y).unwrap();
+                let wide = |(x, y)| <WideMask as Range<u32>>::from_range(x, y).unwrap();
+                let (naive_a, naive_b, wide_a, wide_b) = (naive(a), naive(b), wide(a), wide(b));
+
+                assert_eq!(
+                    wide_a.full_overlap(&wide_b),
+                    naive_a.full_overlap(&naive_b),
+                    "{:?} {:?}",
+                    a,
+                    b
+                );
+                assert_eq!(
+                    wide_a.any_overlap(&wide_b),
+                    naive_a.any_overlap(&naive_b),
+                    "{:?} {:?}",
+                    a,
+                    b
+                );
+            }
+        }
+    }
+
+    /// Sections in `0..=127`, leaning on the ones where the masks change
+    /// words or hit the ends of their shifts
+    fn section() -> impl Strategy<Value = u8> {
+        prop_oneof![
+            Just(0u8),
+            Just(1),
+            Just(63),
+            Just(64),
+            Just(126),
+            Just(127),
+            0..=127u8,
+        ]
+    }
+
+    /// A range the right way round, often a single section
+    fn range() -> impl Strategy<Value = (u8, u8)> {
+        prop_oneof![
+            section().prop_map(|x| (x, x)),
+            (section(), section()).prop_map(|(a, b)| (a.min(b), a.max(b))),
+        ]
+    }
+
+    fn agrees<T: Range<u8>>(a: (u8, u8), b: (u8, u8)) -> Result<(), TestCaseError> {
+        let naive = |(x, y)| NaiveRange::from_range(x, y).unwrap();
+        let other = |(x, y)| T::from_range(x, y).unwrap();
+        let (naive_a, naive_b, a, b) = (naive(a), naive(b), other(a), other(b));
+
+        prop_assert_eq!(a.full_overlap(&b), naive_a.full_overlap(&naive_b));
+        prop_assert_eq!(a.any_overlap(&b), naive_a.any_overlap(&naive_b));
+        Ok(())
+    }
+
+    proptest! {
+        #[test]
+        fn masks_agree_with_naive(a in range(), b in range()) {
+            agrees::<BitMask>(a, b)?;
+            agrees::<U128Mask>(a, b)?;
+            agrees::<WideMask>(a, b)?;
+        }
+
+        #[test]
+        fn a_range_overlaps_itself(a in range()) {
+            let mask = <BitMask as Range<u8>>::from_range(a.0, a.1).unwrap();
+            prop_assert!(mask.full_overlap(&mask) && mask.any_overlap(&mask));
+            prop_assert_eq!(mask.sections().count(), (a.1 - a.0) as usize + 1);
+        }
+
+        #[test]
+        fn rejects_reversed_ranges((a, b) in (section(), section()).prop_filter("reversed", |(a, b)| a > b)) {
+            let text = format!("{}-{}", a, b);
+            let expected = RangeError::Reversed { begin: a as u64, end: b as u64 };
+
+            prop_assert_eq!(<BitMask as Range<u8>>::from_string(&text).unwrap_err(), expected.clone());
+            prop_assert_eq!(<U128Mask as Range<u8>>::from_string(&text).unwrap_err(), expected.clone());
+            prop_assert_eq!(NaiveRange::<u8>::from_string(&text).unwrap_err(), expected);
+        }
+
+        #[test]
+        fn rejects_sections_out_of_range(a in section(), b in 128..=255u8, c in 256..100_000u32) {
+            let too_wide = format!("{}-{}", a, b);
+            prop_assert_eq!(
+                <BitMask as Range<u8>>::from_string(&too_wide).unwrap_err(),
+                RangeError::TooWide { end: b as u64, max: 127 }
+            );
+            prop_assert_eq!(
+                <U128Mask as Range<u8>>::from_string(&too_wide).unwrap_err(),
+                RangeError::TooWide { end: b as u64, max: 127 }
+            );
+            prop_assert!(NaiveRange::<u8>::from_string(&too_wide).is_ok());
+
+            let too_big = format!("{}-{}", a, c);
+            prop_assert_eq!(
+                NaiveRange::<u8>::from_string(&too_big).unwrap_err(),
+                RangeError::BadSection(c.to_string())
+            );
+        }
+
+        #[test]
+        fn rejects_malformed_text(a in section(), b in section(), junk in "[a-z ,.]{1,3}") {
+            for text in [
+                format!("{}", a),
+                format!("{}-", a),
+                format!("-{}", b),
+                format!("{}-{}-{}", a, b, b),
+                format!("{}{}{}", a, junk, b),
+                format!("{}{}-{}", junk, a, b),
+                format!("{}-{}{}", a, b, junk),
+            ] {
+                prop_assert!(<BitMask as Range<u8>>::from_string(&text).is_err(), "{}", text);
+                prop_assert!(NaiveRange::<u8>::from_string(&text).is_err(), "{}", text);
+            }
+        }
+    }
+}
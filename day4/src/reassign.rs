@@ -0,0 +1,238 @@
+use crate::{
+    intervals::IntervalSet,
+    range::{NaiveRange, Overlap},
+};
+
+type Pair = (NaiveRange<u64>, NaiveRange<u64>);
+
+/// What a reassignment has to achieve
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Goal {
+    /// The two elves on a line share no sections
+    Disjoint,
+    /// The two elves still cover exactly the sections they did between
+    /// them, sharing as few as possible
+    KeepCoverage,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reassignment {
+    pub line: usize,
+    pub before: Pair,
+    pub after: Pair,
+    /// How far the boundaries moved in total. Two boundaries can each move
+    /// nearly the whole `u64` range, so this needs more room.
+    pub cost: u128,
+}
+
+/// How far the boundaries of `from` moved to get `to`
+fn distance(from: &NaiveRange<u64>, to: &NaiveRange<u64>) -> u128 {
+    from.0.abs_diff(to.0) as u128 + from.1.abs_diff(to.1) as u128
+}
+
+fn cost(before: &Pair, after: &Pair) -> u128 {
+    distance(&before.0, &after.0) + distance(&before.1, &after.1)
+}
+
+/// Every way of splitting at `cut`, so that `first` ends at or before it
+/// and `second` begins after it, for each cut in `cuts`. Either elf may end
+/// up first. Cuts that `clip` can't make, because there's no section after
+/// them, are skipped.
+fn splits(
+    pair: &Pair,
+    cuts: impl Fn(&Pair) -> Vec<u64>,
+    clip: impl Fn(&Pair, u64) -> Option<Pair>,
+) -> Vec<Pair> {
+    let swapped = (pair.1, pair.0);
+    let mut found = Vec::new();
+
+    for (order, flip) in [(*pair, false), (swapped, true)] {
+        for (first, second) in cuts(&order).into_iter().filter_map(|cut| clip(&order, cut)) {
+            found.push(match flip {
+                false => (first, second),
+                true => (second, first),
+            });
+        }
+    }
+
+    found
+}
+
+/// The cheapest way to stop the elves on a line sharing sections. Any
+/// pair can be separated, so this always finds an answer.
+///
+/// Clipping one elf to end at a cut and the other to begin after it costs a
+/// convex function of the cut, so it's enough to try the cuts where one of
+/// the boundaries starts to move.
+fn separate(pair: &Pair) -> Pair {
+    let cuts = |(a, b): &Pair| {
+        [
+            Some(0),
+            Some(a.0),
+            Some(a.1),
+            b.0.checked_sub(1),
+            b.1.checked_sub(1),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    };
+    let clip = |(a, b): &Pair, cut: u64| {
+        let next = cut.checked_add(1)?;
+        Some((
+            NaiveRange(a.0.min(cut), a.1.min(cut)),
+            NaiveRange(b.0.max(next), b.1.max(next)),
+        ))
+    };
+
+    splits(pair, cuts, clip)
+        .into_iter()
+        .min_by_key(|after| cost(pair, after))
+        .expect("there's always a cut at 0")
+}
+
+/// The cheapest way to split the sections a line covers between its two
+/// elves without sharing any, if the line covers a single stretch of at
+/// least two sections
+fn split_coverage(pair: &Pair) -> Option<Pair> {
+    let (begin, end) = (pair.0 .0.min(pair.1 .0), pair.0 .1.max(pair.1 .1));
+    if !pair.0.any_overlap(&pair.1) || begin == end {
+        return None;
+    }
+
+    // Each elf's cost only changes slope where one of its own boundaries
+    // would be crossed, so those are the cuts worth trying
+    let cuts = |(a, b): &Pair| {
+        [a.0, a.1, b.0.saturating_sub(1), b.1.saturating_sub(1)]
+            .into_iter()
+            .map(|x| x.clamp(begin, end - 1))
+            .collect()
+    };
+    let clip =
+        |_: &Pair, cut: u64| Some((NaiveRange(begin, cut), NaiveRange(cut.checked_add(1)?, end)));
+
+    splits(pair, cuts, clip)
+        .into_iter()
+        .min_by_key(|after| cost(pair, after))
+}
+
+/// Reassigns every line that doesn't meet the `goal`, moving the
+/// boundaries as little as possible. Lines that already meet it, or can't
+/// be improved, are left out.
+pub fn reassign(pairs: &[Pair], goal: Goal) -> Vec<Reassignment> {
+    pairs
+        .iter()
+        .enumerate()
+        .filter(|(_, pair)| pair.0.any_overlap(&pair.1))
+        .filter_map(|(i, pair)| {
+            let after = match goal {
+                Goal::Disjoint => separate(pair),
+                Goal::KeepCoverage => split_coverage(pair)?,
+            };
+
+            Some(Reassignment {
+                line: i + 1,
+                before: *pair,
+                cost: cost(pair, &after),
+                after,
+            })
+        })
+        .collect()
+}
+
+fn coverage(pair: &Pair) -> IntervalSet {
+    IntervalSet::from(pair.0).union(&pair.1.into())
+}
+
+/// Checks that a reassignment really meets its `goal`
+pub fn verify(reassignment: &Reassignment, goal: Goal) -> bool {
+    let (before, after) = (&reassignment.before, &reassignment.after);
+    let valid = [after.0, after.1].iter().all(|x| x.0 <= x.1);
+    let disjoint = !after.0.any_overlap(&after.1);
+
+    valid
+        && disjoint
+        && match goal {
+            Goal::Disjoint => true,
+            Goal::KeepCoverage => coverage(before) == coverage(after),
+        }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pair(a: (u64, u64), b: (u64, u64)) -> Pair {
+        (NaiveRange(a.0, a.1), NaiveRange(b.0, b.1))
+    }
+
+    /// Tries every pair of ranges within `0..=max`
+    fn cheapest(before: &Pair, max: u64, goal: Goal) -> Option<u128> {
+        let ranges: Vec<_> = (0..=max)
+            .flat_map(|a| (a..=max).map(move |b| NaiveRange(a, b)))
+            .collect();
+
+        ranges
+            .iter()
+            .flat_map(|a| ranges.iter().map(move |b| (*a, *b)))
+            .filter(|after| {
+                verify(
+                    &Reassignment {
+                        line: 1,
+                        before: *before,
+                        after: *after,
+                        cost: 0,
+                    },
+                    goal,
+                )
+            })
+            .map(|after| cost(before, &after))
+            .min()
+    }
+
+    #[test]
+    fn finds_the_cheapest_reassignment() {
+        let max = 6;
+        for a in 0..=max {
+            for b in a..=max {
+                for c in 0..=max {
+                    for d in c..=max {
+                        let before = pair((a, b), (c, d));
+                        for goal in [Goal::Disjoint, Goal::KeepCoverage] {
+                            let found = reassign(&[before], goal);
+                            let Some(found) = found.first() else {
+                                let overlap = before.0.any_overlap(&before.1);
+                                assert!(!overlap || cheapest(&before, max, goal).is_none());
+                                continue;
+                            };
+
+                            assert!(verify(found, goal), "{:?}", found);
+                            // Separating may need one section past the
+                            // ones in use, so the brute force gets one more
+                            assert_eq!(Some(found.cost), cheapest(&before, max + 1, goal));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn handles_the_last_section() {
+        let max = u64::MAX;
+        for (before, goal, cost) in [
+            (pair((0, max), (0, max)), Goal::Disjoint, max as u128 + 1),
+            (pair((max, max), (max, max)), Goal::Disjoint, 2),
+            (
+                pair((0, max), (5, max)),
+                Goal::KeepCoverage,
+                max as u128 - 4,
+            ),
+            (pair((max - 1, max), (max, max)), Goal::KeepCoverage, 1),
+        ] {
+            let found = reassign(&[before], goal);
+            assert!(verify(&found[0], goal), "{:?}", found);
+            assert_eq!(found[0].cost, cost, "{:?}", found);
+        }
+    }
+}
@@ -0,0 +1,190 @@
+use crate::{intervals::IntervalSet, range::NaiveRange};
+
+/// One elf's assignment, with where it came from in the file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Assignment {
+    pub line: usize,
+    /// 0 for the first elf on the line, 1 for the second
+    pub elf: usize,
+    pub begin: u64,
+    pub end: u64,
+}
+
+pub fn assignments(pairs: &[(NaiveRange<u64>, NaiveRange<u64>)]) -> Vec<Assignment> {
+    pairs
+        .iter()
+        .enumerate()
+        .flat_map(|(i, (left, right))| {
+            [left, right]
+                .into_iter()
+                .enumerate()
+                .map(move |(elf, x)| Assignment {
+                    line: i + 1,
+                    elf,
+                    begin: x.0,
+                    end: x.1,
+                })
+        })
+        .collect()
+}
+
+/// How many elves cover each stretch of sections, as `(begin, end, depth)`
+/// runs in order. Stretches nobody covers are left out.
+pub fn depths(assignments: &[Assignment]) -> Vec<(u64, u64, usize)> {
+    // Each assignment adds one elf at its beginning and takes one away
+    // just past its end. An assignment that reaches the last possible
+    // section never goes away.
+    let mut events: Vec<(u64, bool)> = assignments
+        .iter()
+        .flat_map(|x| {
+            [
+                Some((x.begin, true)),
+                x.end.checked_add(1).map(|x| (x, false)),
+            ]
+        })
+        .flatten()
+        .collect();
+    events.sort_unstable();
+
+    let mut runs = Vec::new();
+    let (mut depth, mut from) = (0usize, 0u64);
+
+    for (at, start) in events {
+        if depth > 0 && at > from {
+            runs.push((from, at - 1, depth));
+        }
+
+        from = at;
+        match start {
+            true => depth += 1,
+            false => depth -= 1,
+        }
+    }
+
+    if depth > 0 {
+        runs.push((from, u64::MAX, depth));
+    }
+
+    runs
+}
+
+/// The sections covered by more than `k` elves
+pub fn deeper_than(depths: &[(u64, u64, usize)], k: usize) -> IntervalSet {
+    IntervalSet::from_intervals(
+        depths
+            .iter()
+            .filter(|x| x.2 > k)
+            .map(|&(begin, end, _)| (begin, end)),
+    )
+}
+
+/// The most elves covering any one section, and the sections where that
+/// happens
+pub fn max_depth(depths: &[(u64, u64, usize)]) -> (usize, IntervalSet) {
+    let max = depths.iter().map(|x| x.2).max().unwrap_or(0);
+    match max {
+        0 => (0, IntervalSet::new()),
+        _ => (max, deeper_than(depths, max - 1)),
+    }
+}
+
+/// Every pair of assignments on different lines that share a section, as
+/// indices into `assignments`.
+///
+/// Assignments are visited in order of their beginning, keeping those that
+/// haven't ended yet. Each one overlaps exactly the kept assignments, so
+/// this takes `O(n log n + k)` time for `k` overlapping pairs.
+pub fn overlapping_pairs(assignments: &[Assignment]) -> Vec<(usize, usize)> {
+    let mut order: Vec<usize> = (0..assignments.len()).collect();
+    order.sort_unstable_by_key(|x| assignments[*x].begin);
+
+    let mut active: Vec<usize> = Vec::new();
+    let mut pairs = Vec::new();
+
+    for i in order {
+        let x = assignments[i];
+        active.retain(|j| assignments[*j].end >= x.begin);
+
+        pairs.extend(
+            active
+                .iter()
+                .filter(|j| assignments[**j].line != x.line)
+                .map(|j| (*j.min(&i), *j.max(&i))),
+        );
+        active.push(i);
+    }
+
+    pairs.sort_unstable();
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::range::{Overlap, Range};
+
+    const EXAMPLE: &str = "2-4,6-8\n2-3,4-5\n5-7,7-9\n2-8,3-7\n6-6,4-6\n2-6,4-8";
+
+    fn example() -> Vec<Assignment> {
+        let pairs: Vec<_> = EXAMPLE
+            .lines()
+            .map(|x| {
+                let (left, right) = x.split_once(',').unwrap();
+                (
+                    NaiveRange::from_string(left).unwrap(),
+                    NaiveRange::from_string(right).unwrap(),
+                )
+            })
+            .collect();
+        assignments(&pairs)
+    }
+
+    /// Counts the elves on each section one at a time
+    fn brute_depth(assignments: &[Assignment], section: u64) -> usize {
+        assignments
+            .iter()
+            .filter(|x| (x.begin..=x.end).contains(&section))
+            .count()
+    }
+
+    #[test]
+    fn depths_match_brute_force() {
+        let assignments = example();
+        let depths = depths(&assignments);
+
+        for section in 0..12 {
+            let run = depths
+                .iter()
+                .find(|(begin, end, _)| (*begin..=*end).contains(&section));
+            assert_eq!(
+                run.map_or(0, |x| x.2),
+                brute_depth(&assignments, section),
+                "section {}",
+                section
+            );
+        }
+
+        let (max, at) = max_depth(&depths);
+        assert_eq!(max, 8);
+        assert_eq!(at.to_string(), "6");
+        assert_eq!(deeper_than(&depths, 5).to_string(), "4-7");
+    }
+
+    #[test]
+    fn pairs_match_brute_force() {
+        let assignments = example();
+        let mut expected = Vec::new();
+
+        for i in 0..assignments.len() {
+            for j in i + 1..assignments.len() {
+                let (a, b) = (assignments[i], assignments[j]);
+                let overlap = NaiveRange(a.begin, a.end).any_overlap(&NaiveRange(b.begin, b.end));
+                if overlap && a.line != b.line {
+                    expected.push((i, j));
+                }
+            }
+        }
+
+        assert_eq!(overlapping_pairs(&assignments), expected);
+    }
+}
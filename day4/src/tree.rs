@@ -0,0 +1,196 @@
+use crate::sweep::Assignment;
+
+/// A static interval tree over a list of assignments.
+///
+/// The assignments are sorted by their beginning and laid out as an
+/// implicit balanced tree: the root of any slice is its middle element.
+/// Each node also stores the furthest end in its subtree, which lets
+/// queries skip subtrees that finish too early to matter. Every node a
+/// query visits is on the path to a result or next to one, so finding `k`
+/// results takes `O((k + 1) log n)` time, and never more than `O(n)`.
+#[derive(Debug, Clone)]
+pub struct IntervalTree {
+    /// `(begin, end, index into the original assignments)`
+    nodes: Vec<(u64, u64, usize)>,
+    /// The furthest end in the subtree rooted at each node
+    max_end: Vec<u64>,
+}
+
+impl IntervalTree {
+    pub fn new(assignments: &[Assignment]) -> Self {
+        let mut nodes: Vec<_> = assignments
+            .iter()
+            .enumerate()
+            .map(|(i, x)| (x.begin, x.end, i))
+            .collect();
+        nodes.sort_unstable();
+
+        let mut tree = IntervalTree {
+            max_end: vec![0; nodes.len()],
+            nodes,
+        };
+        tree.fill_max_end(0, tree.nodes.len());
+        tree
+    }
+
+    fn fill_max_end(&mut self, lo: usize, hi: usize) -> u64 {
+        if lo >= hi {
+            return 0;
+        }
+
+        let mid = (lo + hi) / 2;
+        let left = self.fill_max_end(lo, mid);
+        let right = self.fill_max_end(mid + 1, hi);
+        self.max_end[mid] = self.nodes[mid].1.max(left).max(right);
+        self.max_end[mid]
+    }
+
+    /// Collects every assignment that begins at or before
+    /// `begin_max` and ends at or after `end_min`
+    fn visit(&self, lo: usize, hi: usize, begin_max: u64, end_min: u64, found: &mut Vec<usize>) {
+        if lo >= hi {
+            return;
+        }
+
+        let mid = (lo + hi) / 2;
+        if self.max_end[mid] < end_min {
+            return;
+        }
+
+        self.visit(lo, mid, begin_max, end_min, found);
+
+        let (begin, end, index) = self.nodes[mid];
+        if begin > begin_max {
+            // Everything to the right begins even later
+            return;
+        }
+        if end >= end_min {
+            found.push(index);
+        }
+
+        self.visit(mid + 1, hi, begin_max, end_min, found);
+    }
+
+    fn query(&self, begin_max: u64, end_min: u64) -> Vec<usize> {
+        let mut found = Vec::new();
+        self.visit(0, self.nodes.len(), begin_max, end_min, &mut found);
+        found.sort_unstable();
+        found
+    }
+
+    /// The assignments that contain `section`
+    pub fn stabbing(&self, section: u64) -> Vec<usize> {
+        self.query(section, section)
+    }
+
+    /// The assignments that share any section with `begin..=end`
+    pub fn overlapping(&self, begin: u64, end: u64) -> Vec<usize> {
+        self.query(end, begin)
+    }
+
+    /// The assignments that contain all of `begin..=end`
+    pub fn containing(&self, begin: u64, end: u64) -> Vec<usize> {
+        self.query(begin, end)
+    }
+}
+
+/// Part 1 as queries: a pair overlaps fully when either assignment is
+/// among those containing the other.
+///
+/// This is much slower than comparing each pair directly: on the puzzle
+/// input most assignments overlap, so every query returns hundreds of
+/// results.
+pub fn part1(assignments: &[Assignment]) -> usize {
+    let tree = IntervalTree::new(assignments);
+    assignments
+        .chunks_exact(2)
+        .enumerate()
+        .filter(|(i, pair)| {
+            let (left, right) = (pair[0], pair[1]);
+            tree.containing(left.begin, left.end).contains(&(i * 2 + 1))
+                || tree.containing(right.begin, right.end).contains(&(i * 2))
+        })
+        .count()
+}
+
+/// Part 2 as queries: a pair overlaps when the second assignment is among
+/// those overlapping the first
+pub fn part2(assignments: &[Assignment]) -> usize {
+    let tree = IntervalTree::new(assignments);
+    assignments
+        .chunks_exact(2)
+        .enumerate()
+        .filter(|(i, pair)| {
+            tree.overlapping(pair[0].begin, pair[0].end)
+                .contains(&(i * 2 + 1))
+        })
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assignments(ranges: &[(u64, u64)]) -> Vec<Assignment> {
+        ranges
+            .iter()
+            .enumerate()
+            .map(|(i, &(begin, end))| Assignment {
+                line: i / 2 + 1,
+                elf: i % 2,
+                begin,
+                end,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn queries_match_brute_force() {
+        let ranges = [
+            (2, 4),
+            (6, 8),
+            (2, 3),
+            (4, 5),
+            (5, 7),
+            (7, 9),
+            (2, 8),
+            (3, 7),
+            (6, 6),
+        ];
+        let tree = IntervalTree::new(&assignments(&ranges));
+        let matching = |f: &dyn Fn(u64, u64) -> bool| -> Vec<usize> {
+            (0..ranges.len())
+                .filter(|i| f(ranges[*i].0, ranges[*i].1))
+                .collect()
+        };
+
+        for a in 0..11 {
+            assert_eq!(tree.stabbing(a), matching(&|x, y| x <= a && a <= y));
+            for b in a..11 {
+                assert_eq!(tree.overlapping(a, b), matching(&|x, y| x <= b && a <= y));
+                assert_eq!(tree.containing(a, b), matching(&|x, y| x <= a && b <= y));
+            }
+        }
+    }
+
+    #[test]
+    fn parts_match_the_example() {
+        let ranges = [
+            (2, 4),
+            (6, 8),
+            (2, 3),
+            (4, 5),
+            (5, 7),
+            (7, 9),
+            (2, 8),
+            (3, 7),
+            (6, 6),
+            (4, 6),
+            (2, 6),
+            (4, 8),
+        ];
+        let assignments = assignments(&ranges);
+        assert_eq!(part1(&assignments), 2);
+        assert_eq!(part2(&assignments), 4);
+    }
+}
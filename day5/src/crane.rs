@@ -0,0 +1,117 @@
+use std::num::NonZeroUsize;
+
+use thiserror::Error;
+
+use crate::parse::Crate;
+
+#[derive(Debug, Error)]
+pub enum CraneError {
+    #[error(r#"Unknown crane "{0}", expected 9000, 9001, grab:<n> or flip"#)]
+    Unknown(String),
+    #[error(r#"A crane must lift at least one crate per grab, found "{0}""#)]
+    BadGrab(String),
+}
+
+/// How a crane carries crates from one stack to another
+pub trait Crane {
+    /// Moves the top `count` crates of `from` onto `to`. `from` always has
+    /// at least `count` crates.
+    fn lift(&self, count: usize, from: &mut Vec<Crate>, to: &mut Vec<Crate>);
+}
+
+/// Lifts one crate at a time, so a move reverses the crates
+#[derive(Debug, Clone, Copy)]
+pub struct CrateMover9000;
+
+/// Lifts every crate at once, so a move keeps their order
+#[derive(Debug, Clone, Copy)]
+pub struct CrateMover9001;
+
+/// Lifts at most this many crates at a time, splitting larger moves into
+/// several grabs that each keep their order
+#[derive(Debug, Clone, Copy)]
+pub struct Grabs(pub NonZeroUsize);
+
+/// Lifts every crate at once, but the top half of the load flips over on
+/// the way. With an odd count the middle crate stays with the bottom half.
+#[derive(Debug, Clone, Copy)]
+pub struct FlipTopHalf;
+
+impl Crane for CrateMover9000 {
+    fn lift(&self, count: usize, from: &mut Vec<Crate>, to: &mut Vec<Crate>) {
+        to.extend(from.drain(from.len() - count..).rev());
+    }
+}
+
+impl Crane for CrateMover9001 {
+    fn lift(&self, count: usize, from: &mut Vec<Crate>, to: &mut Vec<Crate>) {
+        to.extend(from.drain(from.len() - count..));
+    }
+}
+
+impl Crane for Grabs {
+    fn lift(&self, count: usize, from: &mut Vec<Crate>, to: &mut Vec<Crate>) {
+        let mut left = count;
+        while left > 0 {
+            let grab = left.min(self.0.get());
+            to.extend(from.drain(from.len() - grab..));
+            left -= grab;
+        }
+    }
+}
+
+impl Crane for FlipTopHalf {
+    fn lift(&self, count: usize, from: &mut Vec<Crate>, to: &mut Vec<Crate>) {
+        to.extend(from.drain(from.len() - count..));
+        let top = to.len() - count / 2;
+        to[top..].reverse();
+    }
+}
+
+/// Picks a crane by name: `9000`, `9001`, `grab:<n>` or `flip`
+pub fn crane(name: &str) -> Result<Box<dyn Crane>, CraneError> {
+    if let Some(n) = name.strip_prefix("grab:") {
+        let n = n.parse().map_err(|_| CraneError::BadGrab(n.to_string()))?;
+        return Ok(Box::new(Grabs(n)));
+    }
+
+    match name {
+        "9000" => Ok(Box::new(CrateMover9000)),
+        "9001" => Ok(Box::new(CrateMover9001)),
+        "flip" => Ok(Box::new(FlipTopHalf)),
+        _ => Err(CraneError::Unknown(name.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Moves `count` crates off a stack holding `stack`, onto an empty one
+    fn lift(name: &str, stack: &str, count: usize) -> String {
+        let mut from: Vec<_> = stack.bytes().map(Crate).collect();
+        let mut to = Vec::new();
+        crane(name).unwrap().lift(count, &mut from, &mut to);
+
+        from.iter().chain(&to).map(|x| x.0 as char).collect()
+    }
+
+    #[test]
+    fn cranes_lift_differently() {
+        assert_eq!(lift("9000", "ABCDE", 4), "AEDCB");
+        assert_eq!(lift("9001", "ABCDE", 4), "ABCDE");
+        assert_eq!(lift("grab:1", "ABCDE", 4), lift("9000", "ABCDE", 4));
+        assert_eq!(lift("grab:4", "ABCDE", 4), lift("9001", "ABCDE", 4));
+        assert_eq!(lift("grab:3", "ABCDE", 4), "ACDEB");
+        assert_eq!(lift("flip", "ABCDE", 4), "ABCED");
+        assert_eq!(lift("flip", "ABCDE", 5), "ABCED");
+        assert_eq!(lift("flip", "ABCDEF", 0), "ABCDEF");
+    }
+
+    #[test]
+    fn rejects_bad_names() {
+        assert!(matches!(crane("9002"), Err(CraneError::Unknown(_))));
+        assert!(matches!(crane("grab:0"), Err(CraneError::BadGrab(_))));
+        assert!(matches!(crane("grab:x"), Err(CraneError::BadGrab(_))));
+    }
+}
@@ -1,76 +1,104 @@
-use parse::{Crate, Parse};
+use anyhow::{bail, Context, Result};
+use crane::{Crane, CrateMover9000, CrateMover9001};
+use parse::Parse;
+use sim::{Anomaly, OverLong, Simulator};
 
+mod crane;
 mod parse;
+mod sim;
+mod yard;
 
-fn part1(mut parse: Parse) {
-    for mv in parse.moves {
-        let from_index = mv.from - 1;
-        let to_index = mv.to - 1;
-        // SAFETY:
-        // The only unsafe operation in this block is dereferencing to *mut pointer.
-        // However, we know this pointer must be valid as it comes from a `Vec`. We also
-        // know that the &mut references won't alias because we assert that mv.from != mv.to
-        let (from, to) = unsafe {
-            assert!(mv.from != mv.to);
-            (
-                &mut *(parse.yard_cols.get_mut(from_index).unwrap() as *mut Vec<Crate>),
-                &mut *(parse.yard_cols.get_mut(to_index).unwrap() as *mut Vec<Crate>),
-            )
-        };
+const USAGE: &str =
+    "usage: day5 [--crane <9000|9001|grab:<n>|flip>] [--over-long <error|clamp|silent>] [--at <n>[,<n>...]]";
 
-        let drain_range = (from.len().saturating_sub(mv.count))..from.len();
-        to.extend(from.drain(drain_range).rev());
+fn tops(parse: &Parse, crane: &dyn Crane, over_long: OverLong) -> Result<(String, Vec<Anomaly>)> {
+    let outcome = sim::simulate(parse.yard.clone(), &parse.moves, crane, over_long)?;
+    Ok((outcome.yard.tops(), outcome.anomalies))
+}
+
+fn run(input: &'static str) -> Result<()> {
+    let mut custom = None;
+    let mut over_long = OverLong::Clamp;
+    let mut steps = Vec::new();
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--crane" => {
+                let name = args.next().context(USAGE)?;
+                custom = Some((crane::crane(&name)?, name));
+            }
+            "--over-long" => {
+                over_long = match args.next().context(USAGE)?.as_str() {
+                    "error" => OverLong::Error,
+                    "clamp" => OverLong::Clamp,
+                    "silent" => OverLong::Silent,
+                    other => bail!("unknown over-long mode \"{}\"\n{}", other, USAGE),
+                }
+            }
+            "--at" => {
+                for step in args.next().context(USAGE)?.split(',') {
+                    steps.push(
+                        step.parse()
+                            .with_context(|| format!("bad step \"{}\"", step))?,
+                    );
+                }
+            }
+            _ => bail!("unexpected argument \"{}\"\n{}", arg, USAGE),
+        }
     }
 
-    print!("Result for Part 1: ");
-    let out: String = parse
-        .yard_cols
-        .iter()
-        .map(|col| col.last().map(|krate| krate.0 as char).unwrap_or('-'))
-        .collect();
-    println!("{}", out);
-}
+    let parse = parse::parse(input)?;
 
-fn part2(mut parse: Parse) {
-    for mv in parse.moves {
-        let from_index = mv.from - 1;
-        let to_index = mv.to - 1;
-        // SAFETY:
-        // The only unsafe operation in this block is dereferencing to *mut pointer.
-        // However, we know this pointer must be valid as it comes from a `Vec`. We also
-        // know that the &mut references won't alias because we assert that mv.from != mv.to
-        let (from, to) = unsafe {
-            assert!(mv.from != mv.to);
-            (
-                &mut *(parse.yard_cols.get_mut(from_index).unwrap() as *mut Vec<Crate>),
-                &mut *(parse.yard_cols.get_mut(to_index).unwrap() as *mut Vec<Crate>),
-            )
-        };
+    let (part1, anomalies) = tops(&parse, &CrateMover9000, over_long)?;
+    println!("Result for Part 1: {}", part1);
+    println!(
+        "Result for Part 2: {}",
+        tops(&parse, &CrateMover9001, over_long)?.0
+    );
 
-        let drain_range = (from.len().saturating_sub(mv.count))..from.len();
-        to.extend(from.drain(drain_range));
+    if let Some((crane, name)) = &custom {
+        let (result, _) = tops(&parse, crane.as_ref(), over_long)?;
+        println!("Result for crane {}: {}", name, result);
     }
 
-    print!("Result for Part 2: ");
-    let out: String = parse
-        .yard_cols
-        .iter()
-        .map(|col| col.last().map(|krate| krate.0 as char).unwrap_or('-'))
-        .collect();
-    println!("{}", out);
-}
+    // Every crane moves the same number of crates, so the stack heights and
+    // the over-long moves are the same whichever one runs
+    if over_long == OverLong::Clamp && !anomalies.is_empty() {
+        println!("warning: these moves were clamped to the crates available:");
+        for anomaly in anomalies {
+            println!("- {}", anomaly);
+        }
+    }
 
-fn main() {
-    let parse_crate_test = include_str!("input.txt");
+    // Show the yard partway through with the chosen crane, stepping back
+    // and forth between the steps in the order they were given
+    let crane = custom
+        .as_ref()
+        .map_or(&CrateMover9000 as &dyn Crane, |x| x.0.as_ref());
+    let mut sim = Simulator::new(parse.yard.clone(), &parse.moves, crane, over_long);
+    for step in steps {
+        sim.jump(step)?;
+        println!("\nAfter {} moves:\n{}", sim.step(), sim.yard());
 
-    let parse = match parse::parse(parse_crate_test) {
-        Ok(x) => x,
-        Err(err) => {
-            println!("error: {}", err);
-            return;
+        let clamped: Vec<_> = sim
+            .anomalies()
+            .iter()
+            .filter(|x| x.index <= sim.step())
+            .collect();
+        if over_long == OverLong::Clamp && !clamped.is_empty() {
+            println!("warning: these moves so far were clamped to the crates available:");
+            for anomaly in clamped {
+                println!("- {}", anomaly);
+            }
         }
-    };
+    }
+
+    Ok(())
+}
+
+fn main() -> Result<()> {
+    let parse_crate_test = include_str!("input.txt");
 
-    part1(parse.clone());
-    part2(parse);
+    run(parse_crate_test)
 }
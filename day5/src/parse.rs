@@ -5,10 +5,12 @@ use std::{
 };
 use thiserror::Error;
 
+use crate::yard::Yard;
+
 #[derive(Copy, Clone)]
 pub struct Crate(pub u8);
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Move {
     pub count: usize,
     pub from: usize,
@@ -17,7 +19,7 @@ pub struct Move {
 
 #[derive(Debug, Clone)]
 pub struct Parse {
-    pub yard_cols: Vec<Vec<Crate>>,
+    pub yard: Yard,
     pub moves: Vec<Move>,
 }
 
@@ -40,6 +42,12 @@ pub enum ParseError<'a> {
     ExpectedNumber(&'a str),
 }
 
+impl Display for Move {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "move {} from {} to {}", self.count, self.from, self.to)
+    }
+}
+
 impl Crate {
     fn from_char(c: char) -> Option<Self> {
         c.try_into().ok().map(Crate)
@@ -93,18 +101,21 @@ pub fn parse<'a>(input: &'a str) -> Result<Parse, ParseError<'a>> {
 
     // Skip the current line (as it should just be the
     // numbering line)
-    while let Some(x) = char_iter.next() {
+    for x in char_iter.by_ref() {
         if x == '\n' {
             break;
         }
     }
 
-    parse_moves(&char_iter).map(|moves| Parse { yard_cols, moves })
+    parse_moves(&char_iter).map(|moves| Parse {
+        yard: Yard::new(yard_cols),
+        moves,
+    })
 }
 
-fn parse_rows<'a, 'b, 's>(
-    iter: &'a mut Chars<'s>,
-    yard_rows: &'b mut Vec<Vec<Option<Crate>>>,
+fn parse_rows<'s>(
+    iter: &mut Chars<'s>,
+    yard_rows: &mut Vec<Vec<Option<Crate>>>,
 ) -> Result<(), ParseError<'s>> {
     use ParseError as PE;
 
@@ -126,8 +137,12 @@ fn parse_rows<'a, 'b, 's>(
         match curr {
             ' ' => {
                 // If we recieve 3 spaces in a row, we have an empty crate
-                let Some(_) = iter.next_if_eq(&' ') else { continue };
-                let Some(_) = iter.next_if_eq(&' ') else { continue };
+                let Some(_) = iter.next_if_eq(&' ') else {
+                    continue;
+                };
+                let Some(_) = iter.next_if_eq(&' ') else {
+                    continue;
+                };
 
                 push_item(None);
                 // Consume a space if there is one after the crate
@@ -0,0 +1,324 @@
+use std::fmt::Display;
+
+use thiserror::Error;
+
+use crate::{
+    crane::Crane,
+    parse::{Crate, Move},
+    yard::{MoveError, Yard},
+};
+
+/// What to do with a move that asks for more crates than its stack holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverLong {
+    /// Stop the simulation
+    Error,
+    /// Move every crate there is, and report the move afterwards
+    Clamp,
+    /// Move every crate there is without saying anything
+    Silent,
+}
+
+/// A move that asked for more crates than its stack held
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Anomaly {
+    /// Which move this was, counting from 1
+    pub index: usize,
+    pub mv: Move,
+    /// How many crates the stack really held
+    pub available: usize,
+}
+
+impl Display for Anomaly {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Move {} ({}) asks for {} crates, but stack {} only holds {}",
+            self.index, self.mv, self.mv.count, self.mv.from, self.available
+        )
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum SimError {
+    #[error("Move {index} ({mv}) can't be made")]
+    BadMove {
+        index: usize,
+        mv: Move,
+        #[source]
+        error: MoveError,
+    },
+    #[error("{0}")]
+    OverLong(Anomaly),
+    #[error("There's no step {step}, there are only {moves} moves")]
+    NoSuchStep { step: usize, moves: usize },
+}
+
+/// The yard after every move, along with the moves that were too long
+#[derive(Debug, Clone)]
+pub struct Outcome {
+    pub yard: Yard,
+    /// Always empty when over-long moves are errors
+    pub anomalies: Vec<Anomaly>,
+}
+
+/// Steps through the moves one at a time, and back again.
+///
+/// Rather than a copy of the yard for every step, only the crates each
+/// move lifted are kept, in the order they sat on their stack. That's
+/// enough to take the move back, and making it again just runs the crane.
+pub struct Simulator<'a> {
+    yard: Yard,
+    moves: &'a [Move],
+    crane: &'a dyn Crane,
+    over_long: OverLong,
+    /// The crates lifted by every move made so far, including those that
+    /// have since been taken back
+    lifted: Vec<Vec<Crate>>,
+    /// How many moves the yard has been through
+    step: usize,
+    anomalies: Vec<Anomaly>,
+}
+
+impl<'a> Simulator<'a> {
+    pub fn new(yard: Yard, moves: &'a [Move], crane: &'a dyn Crane, over_long: OverLong) -> Self {
+        Simulator {
+            yard,
+            moves,
+            crane,
+            over_long,
+            lifted: Vec::new(),
+            step: 0,
+            anomalies: Vec::new(),
+        }
+    }
+
+    /// How many moves the yard has been through
+    pub fn step(&self) -> usize {
+        self.step
+    }
+
+    /// The yard as it is after `step` moves
+    pub fn yard(&self) -> &Yard {
+        &self.yard
+    }
+
+    /// The over-long moves found so far, including any that have since
+    /// been taken back. Always empty when they're errors.
+    pub fn anomalies(&self) -> &[Anomaly] {
+        &self.anomalies
+    }
+
+    /// Makes the next move, returning false if there are none left
+    pub fn forward(&mut self) -> Result<bool, SimError> {
+        let Some(mv) = self.moves.get(self.step) else {
+            return Ok(false);
+        };
+        let index = self.step + 1;
+
+        // The first time round, check the move and remember what it lifts
+        if self.step == self.lifted.len() {
+            let lifted = check(&self.yard, index, mv, self.over_long, &mut self.anomalies)?;
+            self.lifted.push(lifted.to_vec());
+        }
+
+        make(&mut self.yard, index, mv, self.crane)?;
+        self.step += 1;
+        Ok(true)
+    }
+
+    /// Takes back the last move, returning false if none have been made
+    pub fn back(&mut self) -> bool {
+        let Some(previous) = self.step.checked_sub(1) else {
+            return false;
+        };
+
+        self.yard
+            .undo(&self.moves[previous], &self.lifted[previous])
+            .expect("the move was made, so its stacks exist");
+        self.step = previous;
+        true
+    }
+
+    /// Moves forwards or backwards until the yard has been through `step`
+    /// moves
+    pub fn jump(&mut self, step: usize) -> Result<(), SimError> {
+        if step > self.moves.len() {
+            return Err(SimError::NoSuchStep {
+                step,
+                moves: self.moves.len(),
+            });
+        }
+
+        while self.step > step {
+            self.back();
+        }
+        while self.step < step {
+            self.forward()?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Checks move number `index` against the yard, and returns the crates it
+/// would lift. Only a move that can be made at all counts as over-long, and
+/// unless that's an error it's added to `anomalies`.
+fn check<'y>(
+    yard: &'y Yard,
+    index: usize,
+    mv: &Move,
+    over_long: OverLong,
+    anomalies: &mut Vec<Anomaly>,
+) -> Result<&'y [Crate], SimError> {
+    let lifted = yard.lifts(mv).map_err(|error| bad_move(index, mv, error))?;
+    if mv.count > lifted.len() {
+        let anomaly = Anomaly {
+            index,
+            mv: mv.clone(),
+            available: lifted.len(),
+        };
+        match over_long {
+            OverLong::Error => return Err(SimError::OverLong(anomaly)),
+            OverLong::Clamp | OverLong::Silent => anomalies.push(anomaly),
+        }
+    }
+
+    Ok(lifted)
+}
+
+/// Makes move number `index` with `crane`
+fn make(yard: &mut Yard, index: usize, mv: &Move, crane: &dyn Crane) -> Result<(), SimError> {
+    yard.move_crates(mv, crane)
+        .map_err(|error| bad_move(index, mv, error))
+}
+
+fn bad_move(index: usize, mv: &Move, error: MoveError) -> SimError {
+    SimError::BadMove {
+        index,
+        mv: mv.clone(),
+        error,
+    }
+}
+
+/// Runs every move in turn with `crane`, stopping at the first one the yard
+/// rejects. Unlike [`Simulator`], nothing is kept for stepping back.
+pub fn simulate(
+    mut yard: Yard,
+    moves: &[Move],
+    crane: &dyn Crane,
+    over_long: OverLong,
+) -> Result<Outcome, SimError> {
+    let mut anomalies = Vec::new();
+    for (i, mv) in moves.iter().enumerate() {
+        check(&yard, i + 1, mv, over_long, &mut anomalies)?;
+        make(&mut yard, i + 1, mv, crane)?;
+    }
+
+    Ok(Outcome { yard, anomalies })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        crane::{CrateMover9000, FlipTopHalf},
+        yard::tests::{example, mv},
+    };
+
+    #[test]
+    fn reports_over_long_moves() {
+        let moves = [mv(1, 2, 1), mv(10, 1, 3), mv(2, 2, 1), mv(5, 2, 3)];
+
+        let outcome = simulate(example(), &moves, &CrateMover9000, OverLong::Clamp).unwrap();
+        assert_eq!(outcome.yard.tops(), "M-Z");
+        let found: Vec<_> = outcome
+            .anomalies
+            .iter()
+            .map(|x| (x.index, x.available))
+            .collect();
+        assert_eq!(found, [(2, 3), (4, 0)]);
+
+        let silent = simulate(example(), &moves, &CrateMover9000, OverLong::Silent).unwrap();
+        assert_eq!(silent.yard.tops(), outcome.yard.tops());
+
+        let error = simulate(example(), &moves, &CrateMover9000, OverLong::Error).unwrap_err();
+        assert!(matches!(
+            error,
+            SimError::OverLong(Anomaly { index: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn reports_bad_moves_by_index() {
+        let moves = [mv(1, 2, 1), mv(1, 3, 3)];
+        let error = simulate(example(), &moves, &CrateMover9000, OverLong::Clamp).unwrap_err();
+        assert!(matches!(
+            error,
+            SimError::BadMove {
+                index: 2,
+                error: MoveError::SameStack(3),
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn bad_moves_are_never_over_long() {
+        let moves = [mv(10, 1, 1)];
+        for over_long in [OverLong::Error, OverLong::Clamp] {
+            let mut sim = Simulator::new(example(), &moves, &CrateMover9000, over_long);
+            assert!(matches!(
+                sim.forward(),
+                Err(SimError::BadMove {
+                    error: MoveError::SameStack(1),
+                    ..
+                })
+            ));
+            assert!(sim.anomalies().is_empty());
+        }
+
+        let moves = [mv(10, 4, 1)];
+        let error = simulate(example(), &moves, &CrateMover9000, OverLong::Error).unwrap_err();
+        assert!(matches!(
+            error,
+            SimError::BadMove {
+                error: MoveError::NoSuchStack { stack: 4, .. },
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn steps_back_and_forth() {
+        let moves = [mv(1, 2, 1), mv(10, 1, 3), mv(2, 2, 1), mv(1, 1, 2)];
+        let mut sim = Simulator::new(example(), &moves, &FlipTopHalf, OverLong::Clamp);
+
+        // Every step on the way there, found by running the moves directly
+        let expected: Vec<_> = (0..=moves.len())
+            .map(|n| {
+                let outcome = simulate(example(), &moves[..n], &FlipTopHalf, OverLong::Clamp);
+                outcome.unwrap().yard.to_string()
+            })
+            .collect();
+
+        for step in [4, 0, 2, 3, 1, 4, 2] {
+            sim.jump(step).unwrap();
+            assert_eq!(sim.yard().to_string(), expected[step]);
+        }
+        assert_eq!(sim.anomalies().len(), 1);
+
+        assert!(sim.back());
+        assert_eq!(sim.step(), 1);
+        assert!(matches!(
+            sim.jump(5),
+            Err(SimError::NoSuchStep { step: 5, .. })
+        ));
+        sim.jump(0).unwrap();
+        assert!(!sim.back());
+        assert_eq!(
+            sim.yard().to_string(),
+            "    [D]\n[N] [C]\n[Z] [M] [P]\n 1   2   3"
+        );
+    }
+}
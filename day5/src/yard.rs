@@ -0,0 +1,213 @@
+use std::fmt::Display;
+
+use thiserror::Error;
+
+use crate::{
+    crane::Crane,
+    parse::{Crate, Move},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum MoveError {
+    #[error("Stack {stack} doesn't exist, stacks are numbered 1 to {stacks}")]
+    NoSuchStack { stack: usize, stacks: usize },
+    #[error("Can't move crates from stack {0} onto itself")]
+    SameStack(usize),
+    #[error("Can't move zero crates")]
+    NothingToMove,
+    #[error("Can't take back {lifted} crates, stack {stack} only holds {held}")]
+    CantUndo {
+        stack: usize,
+        lifted: usize,
+        held: usize,
+    },
+}
+
+/// The stacks of crates, bottom first. Stacks are numbered from 1, as they
+/// are in the puzzle input.
+#[derive(Debug, Clone, Default)]
+pub struct Yard {
+    stacks: Vec<Vec<Crate>>,
+}
+
+impl Yard {
+    pub fn new(stacks: Vec<Vec<Crate>>) -> Self {
+        Yard { stacks }
+    }
+
+    /// The crate on top of each stack, with `-` for empty stacks
+    pub fn tops(&self) -> String {
+        self.stacks
+            .iter()
+            .map(|col| col.last().map(|krate| krate.0 as char).unwrap_or('-'))
+            .collect()
+    }
+
+    /// Checks that `mv` can be made, and returns the crates it would lift:
+    /// the top `mv.count` of its stack, or all of them if there are fewer
+    pub fn lifts(&self, mv: &Move) -> Result<&[Crate], MoveError> {
+        if mv.count == 0 {
+            return Err(MoveError::NothingToMove);
+        }
+
+        let (from, _) = self.indices(mv)?;
+        let stack = &self.stacks[from];
+        Ok(&stack[stack.len().saturating_sub(mv.count)..])
+    }
+
+    fn index(&self, stack: usize) -> Result<usize, MoveError> {
+        match (1..=self.stacks.len()).contains(&stack) {
+            true => Ok(stack - 1),
+            false => Err(MoveError::NoSuchStack {
+                stack,
+                stacks: self.stacks.len(),
+            }),
+        }
+    }
+
+    /// The indices of the two stacks a move uses, as `(from, to)`
+    fn indices(&self, mv: &Move) -> Result<(usize, usize), MoveError> {
+        let from = self.index(mv.from)?;
+        let to = self.index(mv.to)?;
+        match from == to {
+            true => Err(MoveError::SameStack(mv.from)),
+            false => Ok((from, to)),
+        }
+    }
+
+    /// Borrows the two stacks a move uses, as `(from, to)`
+    fn two_stacks(&mut self, mv: &Move) -> Result<(&mut Vec<Crate>, &mut Vec<Crate>), MoveError> {
+        let (from, to) = self.indices(mv)?;
+
+        // Whichever stack comes later is the first one in the right half
+        let (left, right) = self.stacks.split_at_mut(from.max(to));
+        Ok(match from < to {
+            true => (&mut left[from], &mut right[0]),
+            false => (&mut right[0], &mut left[to]),
+        })
+    }
+
+    /// Moves the top `mv.count` crates with `crane`, or as many as there are
+    pub fn move_crates(&mut self, mv: &Move, crane: &dyn Crane) -> Result<(), MoveError> {
+        self.lifts(mv)?;
+        let (from, to) = self.two_stacks(mv)?;
+        crane.lift(mv.count.min(from.len()), from, to);
+
+        Ok(())
+    }
+
+    /// Takes back a move that lifted `lifted` off its stack, putting the
+    /// crates back as they were whatever crane moved them
+    pub fn undo(&mut self, mv: &Move, lifted: &[Crate]) -> Result<(), MoveError> {
+        let (from, to) = self.two_stacks(mv)?;
+        let Some(keep) = to.len().checked_sub(lifted.len()) else {
+            return Err(MoveError::CantUndo {
+                stack: mv.to,
+                lifted: lifted.len(),
+                held: to.len(),
+            });
+        };
+        to.truncate(keep);
+        from.extend_from_slice(lifted);
+
+        Ok(())
+    }
+}
+
+/// Draws the yard the way the puzzle input does
+impl Display for Yard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let height = self.stacks.iter().map(Vec::len).max().unwrap_or(0);
+
+        for row in (0..height).rev() {
+            let cells: Vec<_> = self
+                .stacks
+                .iter()
+                .map(|stack| match stack.get(row) {
+                    Some(krate) => krate.to_string(),
+                    None => "   ".to_string(),
+                })
+                .collect();
+            writeln!(f, "{}", cells.join(" ").trim_end())?;
+        }
+
+        let numbers: Vec<_> = (1..=self.stacks.len())
+            .map(|x| format!(" {} ", x))
+            .collect();
+        write!(f, "{}", numbers.join(" ").trim_end())
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::crane::{CrateMover9000, CrateMover9001};
+
+    /// The yard from the puzzle's example, also used by the simulator tests
+    pub(crate) fn example() -> Yard {
+        let stack = |x: &str| x.bytes().map(Crate).collect();
+        Yard::new(vec![stack("ZN"), stack("MCD"), stack("P")])
+    }
+
+    pub(crate) fn mv(count: usize, from: usize, to: usize) -> Move {
+        Move { count, from, to }
+    }
+
+    #[test]
+    fn moves_the_example() {
+        let moves = [mv(1, 2, 1), mv(3, 1, 3), mv(2, 2, 1), mv(1, 1, 2)];
+
+        let cranes: [(&dyn Crane, _); 2] = [(&CrateMover9000, "CMZ"), (&CrateMover9001, "MCD")];
+        for (crane, tops) in cranes {
+            let mut yard = example();
+            for mv in &moves {
+                yard.move_crates(mv, crane).unwrap();
+            }
+            assert_eq!(yard.tops(), tops);
+        }
+    }
+
+    #[test]
+    fn rejects_bad_moves() {
+        let mut yard = example();
+        let mut check = |mv, error| assert_eq!(yard.move_crates(&mv, &CrateMover9001), Err(error));
+
+        check(
+            mv(1, 0, 1),
+            MoveError::NoSuchStack {
+                stack: 0,
+                stacks: 3,
+            },
+        );
+        check(
+            mv(1, 1, 4),
+            MoveError::NoSuchStack {
+                stack: 4,
+                stacks: 3,
+            },
+        );
+        check(mv(1, 2, 2), MoveError::SameStack(2));
+        check(mv(0, 1, 2), MoveError::NothingToMove);
+        assert_eq!(yard.tops(), "NDP");
+    }
+
+    #[test]
+    fn undoes_moves() {
+        let mut yard = example();
+        let lifted = yard.lifts(&mv(2, 2, 3)).unwrap().to_vec();
+        yard.move_crates(&mv(2, 2, 3), &CrateMover9000).unwrap();
+        assert_eq!(yard.tops(), "NMC");
+
+        yard.undo(&mv(2, 2, 3), &lifted).unwrap();
+        assert_eq!(yard.tops(), "NDP");
+        assert_eq!(
+            yard.undo(&mv(2, 2, 3), &lifted),
+            Err(MoveError::CantUndo {
+                stack: 3,
+                lifted: 2,
+                held: 1
+            })
+        );
+        assert_eq!(yard.tops(), "NDP");
+    }
+}